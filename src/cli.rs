@@ -0,0 +1,406 @@
+// コマンドライン引数の解析
+// 名前付きオプションに加えて、従来の位置引数（作業名 メモ 見積時間）もそのまま使える。
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone};
+use std::path::PathBuf;
+use taskstopwatch::tr;
+
+/// --help の文字列（翻訳カタログの usage）
+pub fn usage() -> &'static str {
+    tr!("usage")
+}
+
+pub use taskstopwatch::session::MAX_ESTIMATE_MINUTES;
+
+/// オプションとして扱う引数（-x / --xxx の形でこれ以外のものは不明なオプションとしてエラーにする）
+const FLAGS: &[&str] = &[
+    "-h", "--help", "-V", "--version", "--", "-n", "--name", "-m", "--memo", "--memo-file", "-e",
+    "--estimate", "-u", "--until", "--queue", "--pomodoro", "--tui", "--compact", "--overlay", "--pin",
+    "--no-pin", "--position", "--instance", "--config", "--clipboard",
+];
+
+#[derive(Debug, Default)]
+pub struct Cli {
+    pub name: Option<String>,
+    pub memo: Option<String>,
+    /// メモとして同期する Markdown ファイル（--memo-file）
+    pub memo_file: Option<PathBuf>,
+    /// 所要見積時間[分]
+    pub estimate: Option<u64>,
+    /// 〆の時刻（--until）
+    pub until: Option<DateTime<Local>>,
+    pub queue: Option<PathBuf>,
+    /// ポモドーロモード（--pomodoro）
+    pub pomodoro: bool,
+    /// 端末で表示する（--tui）
+    pub tui: bool,
+    /// 最前面に表示するか（--pin / --no-pin）
+    pub pin: Option<bool>,
+    /// ミニバーで起動する（--compact）
+    pub compact: bool,
+    /// クリックを透過するオーバーレイ表示で起動する（--overlay）
+    pub overlay: bool,
+    /// ウィンドウを置く画面の隅（--position）
+    pub position: Option<taskstopwatch::geometry::Corner>,
+    /// ウィンドウが開いているときの動作（--instance）
+    pub instance: Option<taskstopwatch::control::InstanceMode>,
+    pub config: Option<PathBuf>,
+    pub clipboard: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Run(Cli),
+    /// taskstopwatch ctl ...（実行中のウィンドウへ送るリクエスト）
+    Ctl(taskstopwatch::control::Request),
+    Help,
+    Version,
+}
+
+/// 引数（プログラム名を除く）を解析する
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut cli = Cli::default();
+    let mut positional: Vec<String> = Vec::new();
+    let mut args = args.into_iter().peekable();
+    if args.peek().is_some_and(|a| a == "ctl") {
+        args.next();
+        return parse_ctl(args.collect()).map(Command::Ctl);
+    }
+    let mut only_positional = false;
+    while let Some(arg) = args.next() {
+        // --name=値 の形式にも対応する
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if is_long_flag(f) => (f.to_owned(), Some(v.to_owned())),
+            _ => (arg.clone(), None),
+        };
+        // "- [ ] 手順" のようなメモや "-- 緊急対応" のような作業名は位置引数として扱う
+        // （--estimmate のような打ち間違いは位置引数にせずエラーにする。-- より後はすべて位置引数）
+        if only_positional || (inline.is_none() && !FLAGS.contains(&flag.as_str()) && !looks_like_flag(&arg)) {
+            positional.push(arg);
+            continue;
+        }
+        let mut value = |flag: &str| -> Result<String, String> {
+            match inline.clone().or_else(|| args.next()) {
+                Some(v) => Ok(v),
+                None => Err(tr!("arg_value_missing", flag = flag)),
+            }
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--" => only_positional = true,
+            "-n" | "--name" => set_once(&mut cli.name, value(&flag)?, tr!("arg_name"))?,
+            "-m" | "--memo" => set_once(&mut cli.memo, value(&flag)?, tr!("arg_memo"))?,
+            "--memo-file" => set_once(&mut cli.memo_file, PathBuf::from(value(&flag)?), tr!("arg_memo_file"))?,
+            "-e" | "--estimate" => {
+                let minutes = parse_estimate(&value(&flag)?)?;
+                set_once(&mut cli.estimate, minutes, tr!("arg_estimate"))?;
+            }
+            "-u" | "--until" => {
+                let until = parse_until(&value(&flag)?, Local::now())?;
+                set_once(&mut cli.until, until, tr!("arg_until"))?;
+            }
+            "--queue" => set_once(&mut cli.queue, PathBuf::from(value(&flag)?), tr!("arg_queue"))?,
+            "--pomodoro" if inline.is_none() => cli.pomodoro = true,
+            "--tui" if inline.is_none() => cli.tui = true,
+            "--compact" if inline.is_none() => cli.compact = true,
+            "--overlay" if inline.is_none() => cli.overlay = true,
+            "--pin" if inline.is_none() => set_once(&mut cli.pin, true, "--pin / --no-pin")?,
+            "--no-pin" if inline.is_none() => set_once(&mut cli.pin, false, "--pin / --no-pin")?,
+            "--position" => {
+                let corner = taskstopwatch::geometry::Corner::parse(&value(&flag)?)?;
+                set_once(&mut cli.position, corner, "--position")?;
+            }
+            "--instance" => {
+                let mode = taskstopwatch::control::InstanceMode::parse(&value(&flag)?)?;
+                set_once(&mut cli.instance, mode, "--instance")?;
+            }
+            "--config" => set_once(&mut cli.config, PathBuf::from(value(&flag)?), tr!("arg_config"))?,
+            "--clipboard" => set_once(&mut cli.clipboard, value(&flag)?, tr!("arg_clipboard"))?,
+            _ => return Err(tr!("arg_unknown", arg = arg)),
+        }
+    }
+
+    // 位置引数: 作業名 メモ 見積時間
+    if positional.len() > 3 {
+        return Err(tr!("arg_too_many", args = positional[3..].join(" ")));
+    }
+    let mut positional = positional.into_iter();
+    if let Some(name) = positional.next() {
+        set_once(&mut cli.name, name, tr!("arg_name"))?;
+    }
+    if let Some(memo) = positional.next() {
+        set_once(&mut cli.memo, memo, tr!("arg_memo"))?;
+    }
+    if let Some(estimate) = positional.next() {
+        set_once(&mut cli.estimate, parse_estimate(&estimate)?, tr!("arg_estimate"))?;
+    }
+    if cli.memo.is_some() && cli.memo_file.is_some() {
+        return Err(tr!("arg_memo_conflict").to_owned());
+    }
+    if cli.estimate.is_some() && cli.until.is_some() {
+        return Err(tr!("arg_until_conflict").to_owned());
+    }
+    Ok(Command::Run(cli))
+}
+
+/// ctl のコマンドを解析する
+fn parse_ctl(args: Vec<String>) -> Result<taskstopwatch::control::Request, String> {
+    use taskstopwatch::control::Request;
+    let Some((cmd, rest)) = args.split_first() else {
+        return Err(tr!("ctl_missing").to_owned());
+    };
+    let text = rest.join(" ");
+    let no_args = |request: Request| {
+        if rest.is_empty() {
+            Ok(request)
+        } else {
+            Err(tr!("ctl_no_args", cmd = cmd, args = text))
+        }
+    };
+    let required = |what: &str| {
+        if text.trim().is_empty() {
+            Err(tr!("ctl_required", cmd = cmd, what = what))
+        } else {
+            Ok(text.clone())
+        }
+    };
+    match cmd.as_str() {
+        "status" => no_args(Request::Status),
+        "pause" => no_args(Request::Pause),
+        "resume" => no_args(Request::Resume),
+        "done" => no_args(Request::Done),
+        "name" => Ok(Request::SetName { name: required(tr!("ctl_arg_name"))? }),
+        "memo" => Ok(Request::AppendMemo { text: required(tr!("ctl_arg_text"))? }),
+        "tick" => {
+            let item = required(tr!("ctl_arg_number"))?
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n >= 1)
+                .ok_or_else(|| tr!("item_number_invalid", value = text))?;
+            Ok(Request::Tick { item })
+        }
+        "extend" => {
+            let minutes = parse_estimate(&required(tr!("ctl_arg_time"))?)?;
+            if minutes == 0 {
+                return Err(tr!("extend_too_short").to_owned());
+            }
+            Ok(Request::Extend { minutes })
+        }
+        "overlay" => {
+            let enabled = match text.trim() {
+                "" | "toggle" => None,
+                "on" => Some(true),
+                "off" => Some(false),
+                _ => return Err(tr!("ctl_overlay_invalid", value = text)),
+            };
+            Ok(Request::Overlay { enabled })
+        }
+        _ => Err(tr!("ctl_unknown", cmd = cmd)),
+    }
+}
+
+// "--name" のような形（= の前の部分）か
+// -x や --xxx のようにオプションに見える引数か
+fn looks_like_flag(s: &str) -> bool {
+    let rest = s.strip_prefix("--").or_else(|| s.strip_prefix('-'));
+    rest.and_then(|r| r.chars().next()).is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+fn is_long_flag(s: &str) -> bool {
+    s.strip_prefix("--")
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+fn set_once<T>(slot: &mut Option<T>, value: T, what: &str) -> Result<(), String> {
+    if slot.is_some() {
+        return Err(tr!("arg_twice", what = what));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// "90" / "25m" / "1h30m" / "2h" を分に変換する
+pub fn parse_estimate(s: &str) -> Result<u64, String> {
+    let invalid = || tr!("estimate_invalid", value = format!("{:?}", s));
+    let text = s.trim().to_ascii_lowercase();
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    let minutes = if text.chars().all(|c| c.is_ascii_digit()) {
+        text.parse::<u64>().map_err(|_| invalid())?
+    } else {
+        let mut total: u64 = 0;
+        let mut digits = String::new();
+        let mut seen_h = false;
+        let mut seen_m = false;
+        for c in text.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                'h' | 'm' => {
+                    let n: u64 = digits.parse().map_err(|_| invalid())?;
+                    digits.clear();
+                    // 1h30m の順のみ受け付ける
+                    if c == 'h' && !seen_h && !seen_m {
+                        seen_h = true;
+                        total = n.checked_mul(60).ok_or_else(invalid)?;
+                    } else if c == 'm' && !seen_m {
+                        seen_m = true;
+                        total = total.checked_add(n).ok_or_else(invalid)?;
+                    } else {
+                        return Err(invalid());
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        if !digits.is_empty() {
+            return Err(invalid());
+        }
+        total
+    };
+    if minutes > MAX_ESTIMATE_MINUTES {
+        return Err(tr!("estimate_too_long", value = s, max = MAX_ESTIMATE_MINUTES));
+    }
+    Ok(minutes)
+}
+
+/// "14:00" / "14:00:30" / "2025-12-24 17:30" / "2025-12-24T17:30" を〆の日時に変換する。
+/// 時刻だけの場合は now 以降で最も近いその時刻（過ぎていれば翌日）とする。
+pub fn parse_until(s: &str, now: DateTime<Local>) -> Result<DateTime<Local>, String> {
+    let text = s.trim();
+    let invalid = || tr!("until_invalid", value = format!("{:?}", s));
+    let naive = if let Some(t) = ["%H:%M", "%H:%M:%S"]
+        .iter()
+        .find_map(|f| NaiveTime::parse_from_str(text, f).ok())
+    {
+        let today = now.date_naive().and_time(t);
+        if today > now.naive_local() {
+            today
+        } else {
+            // 過ぎた時刻は翌日（日付をまたぐ〆）
+            today + chrono::Duration::days(1)
+        }
+    } else {
+        ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(text, f).ok())
+            .ok_or_else(invalid)?
+    };
+    // 夏時間の切り替えで存在しない時刻は、その直後として扱う
+    let until = Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest())
+        .ok_or_else(invalid)?;
+    if until <= now {
+        return Err(tr!("until_past", value = s));
+    }
+    if until - now > chrono::Duration::minutes(MAX_ESTIMATE_MINUTES as i64) {
+        return Err(tr!("until_too_far", value = s, max = MAX_ESTIMATE_MINUTES));
+    }
+    Ok(until)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Cli, String> {
+        match parse(args.iter().map(|a| a.to_string()))? {
+            Command::Run(cli) => Ok(cli),
+            other => panic!("Run ではありません: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_estimate_formats() {
+        assert_eq!(parse_estimate("90"), Ok(90));
+        assert_eq!(parse_estimate("25m"), Ok(25));
+        assert_eq!(parse_estimate("2h"), Ok(120));
+        assert_eq!(parse_estimate(" 1H30M "), Ok(90));
+        assert_eq!(parse_estimate("0"), Ok(0));
+    }
+
+    #[test]
+    fn parse_estimate_rejects_invalid() {
+        for s in ["", "abc", "30m1h", "1h1h", "1h30", "m", "-5", "1.5h"] {
+            assert!(parse_estimate(s).is_err(), "{:?}", s);
+        }
+        assert_eq!(parse_estimate(&MAX_ESTIMATE_MINUTES.to_string()), Ok(MAX_ESTIMATE_MINUTES));
+        assert!(parse_estimate(&(MAX_ESTIMATE_MINUTES + 1).to_string()).is_err());
+        assert!(parse_estimate("99999999999999999999h").is_err());
+    }
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn parse_until_time_of_day() {
+        let now = at(2024, 5, 1, 10, 0);
+        assert_eq!(parse_until("14:00", now), Ok(at(2024, 5, 1, 14, 0)));
+        assert_eq!(parse_until("10:00:30", now), Ok(now + chrono::Duration::seconds(30)));
+        // 過ぎた時刻は翌日
+        assert_eq!(parse_until("9:30", now), Ok(at(2024, 5, 2, 9, 30)));
+        assert_eq!(parse_until("10:00", now), Ok(at(2024, 5, 2, 10, 0)));
+    }
+
+    #[test]
+    fn parse_until_date_time() {
+        let now = at(2024, 5, 1, 10, 0);
+        assert_eq!(parse_until("2024-05-03 17:30", now), Ok(at(2024, 5, 3, 17, 30)));
+        assert_eq!(parse_until("2024-05-03T17:30", now), Ok(at(2024, 5, 3, 17, 30)));
+        assert!(parse_until("2024-04-30 17:30", now).is_err());
+        assert!(parse_until("2024-06-01 10:00", now).is_err());
+        assert!(parse_until("25:00", now).is_err());
+        assert!(parse_until("tomorrow", now).is_err());
+    }
+
+    #[test]
+    fn parse_positional_and_named() {
+        let cli = run(&["作業", "メモ", "1h"]).unwrap();
+        assert_eq!(cli.name.as_deref(), Some("作業"));
+        assert_eq!(cli.memo.as_deref(), Some("メモ"));
+        assert_eq!(cli.estimate, Some(60));
+
+        let cli = run(&["--name=作業", "-m", "メモ", "--estimate", "25m", "--tui"]).unwrap();
+        assert_eq!(cli.name.as_deref(), Some("作業"));
+        assert_eq!(cli.memo.as_deref(), Some("メモ"));
+        assert_eq!(cli.estimate, Some(25));
+        assert!(cli.tui);
+    }
+
+    #[test]
+    fn parse_dash_prefixed_positionals() {
+        let cli = run(&["-- 緊急対応", "- [ ] STEP1<br>- [ ] STEP2"]).unwrap();
+        assert_eq!(cli.name.as_deref(), Some("-- 緊急対応"));
+        assert_eq!(cli.memo.as_deref(), Some("- [ ] STEP1<br>- [ ] STEP2"));
+        let cli = run(&["--", "--tui", "-x"]).unwrap();
+        assert_eq!(cli.name.as_deref(), Some("--tui"));
+        assert_eq!(cli.memo.as_deref(), Some("-x"));
+        assert!(!cli.tui);
+        assert!(run(&["-", "-5分の作業"]).is_ok());
+    }
+
+    #[test]
+    fn parse_rejects_conflicts_and_unknown() {
+        assert!(run(&["-n", "a", "b"]).is_err());
+        assert!(run(&["--name"]).is_err());
+        assert!(run(&["--unknown=1"]).is_err());
+        assert_eq!(run(&["--estimmate", "30"]).unwrap_err(), tr!("arg_unknown", arg = "--estimmate"));
+        assert!(run(&["作業", "-x"]).is_err());
+        assert!(run(&["--tui=1"]).is_err());
+        assert!(run(&["a", "b", "30", "extra"]).is_err());
+        assert!(run(&["-e", "30", "-u", "23:59"]).is_err());
+        assert!(run(&["-m", "x", "--memo-file", "x.md"]).is_err());
+    }
+
+    #[test]
+    fn parse_help_and_ctl() {
+        assert!(matches!(parse(["--help".to_owned()]), Ok(Command::Help)));
+        let ctl = parse(["ctl", "tick", "2"].map(String::from)).unwrap();
+        assert!(matches!(ctl, Command::Ctl(taskstopwatch::control::Request::Tick { item: 2 })));
+        assert!(parse(["ctl", "tick", "0"].map(String::from)).is_err());
+    }
+}
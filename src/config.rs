@@ -0,0 +1,521 @@
+// 設定ファイル（TOML）
+// 既定では XDG 設定ディレクトリの taskstopwatch/config.toml を読み込む（--config で変更可）。
+// すべての項目は省略可能で、省略した項目は従来の既定値になる。
+//
+//   repaint_interval_ms = 200
+//
+//   [colors]
+//   background = "#fffa00"
+//   overtime = "#ffc8c8"
+//
+//   [font_sizes]
+//   elapsed = 20.0
+use crate::tr;
+use eframe::egui::{self, Color32};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// "#RRGGBB" 形式の色
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Rgb(pub Color32);
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let hex = s.strip_prefix('#').unwrap_or(&s);
+        let valid = hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(tr!("color_invalid", value = format!("{:?}", s)));
+        }
+        let v = u32::from_str_radix(hex, 16).map_err(|e| e.to_string())?;
+        Ok(Rgb(Color32::from_rgb((v >> 16) as u8, (v >> 8) as u8, v as u8)))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    /// 通常時の背景色
+    pub background: Rgb,
+    /// 見積時間を超過したときの背景色
+    pub overtime: Rgb,
+    /// 一時停止中の背景色
+    pub paused: Rgb,
+    /// egui のウィンドウ/パネルの塗りつぶし色
+    pub window_fill: Rgb,
+    /// 文字色
+    pub text: Rgb,
+    /// 〆（終了時刻）の文字色
+    pub deadline: Rgb,
+    /// ポモドーロの休憩中の背景色
+    pub short_break: Rgb,
+    /// ポモドーロの長い休憩中の背景色
+    pub long_break: Rgb,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            background: Rgb(Color32::from_rgb(255, 250, 0)),
+            overtime: Rgb(Color32::from_rgb(255, 200, 200)),
+            paused: Rgb(Color32::from_rgb(200, 225, 255)),
+            window_fill: Rgb(Color32::from_rgb(255, 255, 0)),
+            text: Rgb(Color32::BLACK),
+            deadline: Rgb(Color32::RED),
+            short_break: Rgb(Color32::from_rgb(200, 240, 200)),
+            long_break: Rgb(Color32::from_rgb(160, 225, 190)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontSizes {
+    /// 〆（終了時刻）
+    pub deadline: f32,
+    /// 残り時間 / 超過時間
+    pub remaining: f32,
+    /// 経過時間
+    pub elapsed: f32,
+    /// 作業名
+    pub name: f32,
+}
+
+impl Default for FontSizes {
+    fn default() -> Self {
+        Self {
+            deadline: 22.0,
+            remaining: 14.0,
+            elapsed: 20.0,
+            name: 22.0,
+        }
+    }
+}
+
+/// 表示に使うフォント（[font]）
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontConfig {
+    /// フォント名（Linux などでは fontconfig で探す。例: "Noto Sans CJK JP"）
+    pub family: Option<String>,
+    /// フォントファイル（family より優先）
+    pub file: Option<PathBuf>,
+    /// フォントファイルが複数のフォントを含む（.ttc）場合の番号
+    pub index: u32,
+    /// メモやボタンなどの文字の大きさ（省略すると egui の既定）
+    pub size: Option<f32>,
+    /// 足りない文字を探すフォント名（指定したフォントと日本語のフォントの後、中国語・韓国語のフォントの前に使う）
+    pub fallbacks: Vec<String>,
+}
+
+/// ウィンドウの大きさなど
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    /// 幅（自動で合わせるときは最小の幅）
+    pub width: f32,
+    /// 自動で合わせるときの最大の幅
+    pub max_width: f32,
+    /// メモなどの表示内容に合わせて大きさを変える（メモを編集したときも合わせ直す）
+    pub auto_size: bool,
+    /// ヘッダ、タイマー、余白などの固定部分の高さ
+    pub base_height: f32,
+    /// メモ1行あたりの高さ
+    pub line_height: f32,
+    /// 完了ボタンと余白のための高さ
+    pub button_padding: f32,
+    pub min_height: f32,
+    pub max_height: f32,
+    /// 常に最前面に表示する（画面の「最前面」ボタンや Ctrl+T でも切り替えられる）
+    pub always_on_top: bool,
+    /// ミニバーの幅と高さ
+    pub compact_width: f32,
+    pub compact_height: f32,
+    /// 背景の不透明度（0.1〜1.0）
+    pub opacity: f32,
+    /// クリックを透過するオーバーレイ表示のときの背景の不透明度
+    pub overlay_opacity: f32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 300.0,
+            max_width: 800.0,
+            auto_size: true,
+            base_height: 140.0,
+            line_height: 22.0,
+            button_padding: 80.0,
+            min_height: 140.0,
+            max_height: 1400.0,
+            always_on_top: true,
+            compact_width: 260.0,
+            compact_height: 36.0,
+            opacity: 1.0,
+            overlay_opacity: 0.6,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// メモの行数から初期の高さを求める
+    pub fn height_for(&self, memo_lines: usize) -> f32 {
+        let memo_area = (memo_lines.max(1) as f32) * self.line_height;
+        (self.base_height + memo_area + self.button_padding).clamp(self.min_height, self.max_height)
+    }
+
+    /// 表示内容の大きさから、それが収まるウィンドウの大きさを求める
+    pub fn fit(&self, content: egui::Vec2) -> egui::Vec2 {
+        egui::vec2(
+            content.x.ceil().clamp(self.width, self.max_width.max(self.width)),
+            content.y.ceil().clamp(self.min_height, self.max_height),
+        )
+    }
+}
+
+/// 完了時にクリップボードへ入れる内容
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    /// プリセット名またはテンプレート文字列
+    pub template: String,
+    /// ユーザー定義のプリセット（名前 = テンプレート）
+    pub presets: BTreeMap<String, String>,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            template: crate::template::DEFAULT_PRESET.to_owned(),
+            presets: BTreeMap::new(),
+        }
+    }
+}
+
+impl ClipboardConfig {
+    /// 使うテンプレートを決める（--clipboard の指定があればそちらを優先）
+    pub fn resolve(&self, cli: Option<&str>) -> Result<crate::template::Template, String> {
+        crate::template::resolve(cli.unwrap_or(&self.template), &self.presets)
+    }
+}
+
+/// ポモドーロモード（--pomodoro または enabled = true で有効）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PomodoroConfig {
+    pub enabled: bool,
+    /// 作業時間[分]
+    pub work_minutes: u64,
+    /// 休憩時間[分]
+    pub short_break_minutes: u64,
+    /// 長い休憩の時間[分]
+    pub long_break_minutes: u64,
+    /// 長い休憩までの作業の回数
+    pub cycles: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            cycles: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub colors: Colors,
+    pub font_sizes: FontSizes,
+    pub font: FontConfig,
+    pub window: WindowConfig,
+    pub clipboard: ClipboardConfig,
+    pub pomodoro: PomodoroConfig,
+    /// 時間・時刻の表示形式
+    pub format: crate::format::Format,
+    /// ウィンドウのタイトルの形式（{time} {status} {name} {elapsed} {deadline}。省略すると言語ごとの既定）
+    pub title: Option<String>,
+    /// 表示する言語（ja / en。省略すると環境変数 LANG などから決める）
+    pub language: Option<crate::i18n::Language>,
+    /// すでにウィンドウが開いているときに起動した場合の動作（new / queue / replace）
+    pub instance: crate::control::InstanceMode,
+    /// イベントごとに実行するコマンド（[[hooks]]）
+    pub hooks: Vec<crate::hooks::Hook>,
+    /// 画面の再描画間隔[ミリ秒]
+    pub repaint_interval_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            colors: Colors::default(),
+            font_sizes: FontSizes::default(),
+            font: FontConfig::default(),
+            window: WindowConfig::default(),
+            clipboard: ClipboardConfig::default(),
+            pomodoro: PomodoroConfig::default(),
+            format: crate::format::Format::default(),
+            title: None,
+            language: None,
+            instance: crate::control::InstanceMode::default(),
+            hooks: Vec::new(),
+            repaint_interval_ms: 200,
+        }
+    }
+}
+
+impl Config {
+    fn validate(&self) -> Result<(), String> {
+        let sizes = [
+            ("font_sizes.deadline", self.font_sizes.deadline),
+            ("font_sizes.remaining", self.font_sizes.remaining),
+            ("font_sizes.elapsed", self.font_sizes.elapsed),
+            ("font_sizes.name", self.font_sizes.name),
+        ];
+        let sizes = sizes.into_iter().chain(self.font.size.map(|v| ("font.size", v)));
+        for (key, v) in sizes {
+            if !(4.0..=200.0).contains(&v) {
+                return Err(out_of_range(key, 4, 200, v));
+            }
+        }
+        let w = &self.window;
+        if !(100.0..=4000.0).contains(&w.width) {
+            return Err(out_of_range("window.width", 100, 4000, w.width));
+        }
+        if !(100.0..=8000.0).contains(&w.max_width) {
+            return Err(out_of_range("window.max_width", 100, 8000, w.max_width));
+        }
+        if !(60.0..=4000.0).contains(&w.compact_width) {
+            return Err(out_of_range("window.compact_width", 60, 4000, w.compact_width));
+        }
+        if !(16.0..=400.0).contains(&w.compact_height) {
+            return Err(out_of_range("window.compact_height", 16, 400, w.compact_height));
+        }
+        for (key, v) in [("window.opacity", w.opacity), ("window.overlay_opacity", w.overlay_opacity)] {
+            if !(0.1..=1.0).contains(&v) {
+                return Err(out_of_range(key, 0.1, 1.0, v));
+            }
+        }
+        let lengths = [
+            ("window.base_height", w.base_height),
+            ("window.line_height", w.line_height),
+            ("window.button_padding", w.button_padding),
+            ("window.min_height", w.min_height),
+        ];
+        for (key, v) in lengths {
+            if !v.is_finite() || v < 0.0 {
+                return Err(tr!("config_negative", key = key, value = v));
+            }
+        }
+        if !w.max_height.is_finite() || w.max_height < w.min_height {
+            return Err(tr!("config_max_height", max = w.max_height, min = w.min_height));
+        }
+        if let Some(title) = &self.title {
+            let fields = title.split('{').skip(1).filter_map(|s| s.split_once('}').map(|(f, _)| f));
+            for field in fields {
+                if !crate::session::TITLE_FIELDS.contains(&field) {
+                    let fields = crate::session::TITLE_FIELDS.iter().map(|f| format!("{{{}}}", f));
+                    return Err(tr!(
+                        "config_title_field",
+                        field = field,
+                        fields = fields.collect::<Vec<_>>().join(" "),
+                    ));
+                }
+            }
+        }
+        for (name, t) in &self.clipboard.presets {
+            crate::template::Template::parse(t)
+                .map_err(|e| format!("clipboard.presets.{}: {}", name, e))?;
+        }
+        self.clipboard
+            .resolve(None)
+            .map_err(|e| format!("clipboard.template: {}", e))?;
+        let p = &self.pomodoro;
+        let minutes = [
+            ("pomodoro.work_minutes", p.work_minutes),
+            ("pomodoro.short_break_minutes", p.short_break_minutes),
+            ("pomodoro.long_break_minutes", p.long_break_minutes),
+        ];
+        for (key, v) in minutes {
+            if !(1..=600).contains(&v) {
+                return Err(out_of_range(key, 1, 600, v));
+            }
+        }
+        if !(1..=20).contains(&p.cycles) {
+            return Err(out_of_range("pomodoro.cycles", 1, 20, p.cycles));
+        }
+        for (i, hook) in self.hooks.iter().enumerate() {
+            hook.validate().map_err(|e| format!("hooks[{}]: {}", i, e))?;
+        }
+        if !(10..=10_000).contains(&self.repaint_interval_ms) {
+            return Err(out_of_range("repaint_interval_ms", 10, 10_000, self.repaint_interval_ms));
+        }
+        Ok(())
+    }
+}
+
+// 設定値が範囲外のときのメッセージ
+fn out_of_range(key: &str, min: impl Display, max: impl Display, value: impl Display) -> String {
+    tr!("config_out_of_range", key = key, min = min, max = max, value = value)
+}
+
+/// 既定の設定ファイルのパス（例: ~/.config/taskstopwatch/config.toml）
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("taskstopwatch").join("config.toml"))
+}
+
+/// 設定ファイルを読み込んで検証する
+pub fn load(path: &Path) -> Result<Config, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| tr!("config_read_failed", path = path.display(), error = e))?;
+    let config: Config = toml::from_str(&text)
+        .map_err(|e| tr!("config_parse_failed", path = path.display(), error = e))?;
+    config
+        .validate()
+        .map_err(|e| tr!("config_invalid", path = path.display(), error = e))?;
+    Ok(config)
+}
+
+/// 起動時の読み込み。--config で指定されたファイルは必須、既定の場所のファイルは無ければ既定値を使う。
+pub fn load_startup(explicit: Option<&Path>) -> Result<(Config, Option<PathBuf>), String> {
+    if let Some(path) = explicit {
+        return Ok((load(path)?, Some(path.to_path_buf())));
+    }
+    match default_path() {
+        Some(path) if path.exists() => Ok((load(&path)?, Some(path))),
+        other => Ok((Config::default(), other)),
+    }
+}
+
+/// 設定ファイルの更新を検知して再読み込みする
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self { path, modified }
+    }
+
+    /// ファイルが更新されていれば読み込み結果を返す
+    pub fn poll(&mut self) -> Option<Result<Config, String>> {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        // 削除された場合は既定値に戻す
+        if modified.is_none() {
+            return Some(Ok(Config::default()));
+        }
+        Some(load(&self.path))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(src: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(src).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(check("").is_ok());
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn out_of_range_values() {
+        assert_eq!(
+            check("[window]\nopacity = 0.05").unwrap_err(),
+            out_of_range("window.opacity", 0.1, 1.0, 0.05)
+        );
+        assert_eq!(
+            check("[font_sizes]\nelapsed = 2.0").unwrap_err(),
+            out_of_range("font_sizes.elapsed", 4, 200, 2.0)
+        );
+        assert_eq!(
+            check("[pomodoro]\ncycles = 0").unwrap_err(),
+            out_of_range("pomodoro.cycles", 1, 20, 0)
+        );
+        assert!(check("repaint_interval_ms = 5").is_err());
+        assert!(check("[window]\nopacity = 1.0\noverlay_opacity = 0.1").is_ok());
+    }
+
+    #[test]
+    fn window_heights() {
+        assert_eq!(
+            check("[window]\nline_height = -1.0").unwrap_err(),
+            tr!("config_negative", key = "window.line_height", value = -1.0)
+        );
+        assert_eq!(
+            check("[window]\nmin_height = 200.0\nmax_height = 100.0").unwrap_err(),
+            tr!("config_max_height", max = 100.0, min = 200.0)
+        );
+        assert!(check("[window]\nmin_height = 100.0\nmax_height = 100.0").is_ok());
+    }
+
+    #[test]
+    fn colors() {
+        let config = check("[colors]\nbackground = \"#FFaa00\"\ntext = \"000000\"").unwrap();
+        assert_eq!(config.colors.background.0, Color32::from_rgb(0xff, 0xaa, 0x00));
+        assert_eq!(config.colors.text.0, Color32::BLACK);
+        for bad in ["#12345", "#1234567", "#GGGGGG", "red"] {
+            let err = check(&format!("[colors]\nbackground = {:?}", bad)).unwrap_err();
+            assert!(err.contains(&tr!("color_invalid", value = format!("{:?}", bad))), "{}", err);
+        }
+    }
+
+    #[test]
+    fn hooks() {
+        let hook = |body: &str| check(&format!("[[hooks]]\n{}", body));
+        assert!(hook("event = \"progress\"\ncommand = \"x\"\npercent = 80").is_ok());
+        assert!(hook("event = \"overtime\"\ncommand = \"x\"\nevery_minutes = 5").is_ok());
+        assert_eq!(
+            hook("event = \"done\"\ncommand = \" \"").unwrap_err(),
+            format!("hooks[0]: {}", tr!("hook_command_empty"))
+        );
+        assert_eq!(
+            hook("event = \"progress\"\ncommand = \"x\"").unwrap_err(),
+            format!("hooks[0]: {}", tr!("hook_percent_required"))
+        );
+        assert!(hook("event = \"progress\"\ncommand = \"x\"\npercent = 0").is_err());
+        assert_eq!(
+            hook("event = \"overtime\"\ncommand = \"x\"\nevery_minutes = 0").unwrap_err(),
+            format!("hooks[0]: {}", tr!("hook_every_required"))
+        );
+        assert_eq!(
+            hook("event = \"deadline\"\ncommand = \"x\"\npercent = 50").unwrap_err(),
+            format!("hooks[0]: {}", tr!("hook_percent_only", event = "deadline"))
+        );
+        assert_eq!(
+            hook("event = \"start\"\ncommand = \"x\"\nevery_minutes = 5").unwrap_err(),
+            format!("hooks[0]: {}", tr!("hook_every_only", event = "start"))
+        );
+    }
+
+    #[test]
+    fn title_placeholders() {
+        assert!(check("title = \"{time} {name}\"").is_ok());
+        assert!(check("title = \"{unknown}\"").is_err());
+    }
+}
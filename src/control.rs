@@ -0,0 +1,235 @@
+// ローカル制御 API
+// 実行中のウィンドウを Unix ドメインソケット経由で操作する（1行1リクエストの JSON、応答も1行の JSON）。
+//
+//   $ echo '{"cmd":"status"}' | nc -U $XDG_RUNTIME_DIR/taskstopwatch/control.sock
+//   $ taskstopwatch ctl pause
+//
+// ソケットのスレッドは受け取ったリクエストを GUI スレッドへ渡し、update で処理した結果を返す。
+use crate::history::ChecklistItem;
+use crate::queue::QueuedTask;
+use crate::tr;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+/// 制御コマンド
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Pause,
+    Resume,
+    /// 「完了!」と同じ
+    Done,
+    SetName { name: String },
+    /// メモの末尾に行を追加する
+    AppendMemo { text: String },
+    /// チェックリストの item 番目（1始まり）にチェックを付ける
+    Tick { item: usize },
+    /// 〆を minutes 分延ばす（〆がなければ今から minutes 分後を〆にする）
+    Extend { minutes: u64 },
+    /// クリックを透過するオーバーレイ表示にする / やめる（enabled がなければ切り替える）
+    Overlay {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        enabled: Option<bool>,
+    },
+    /// 2つ目の起動から渡された作業。replace なら今の作業を終えてすぐに始め、そうでなければキューに追加する
+    Open { tasks: Vec<QueuedTask>, replace: bool },
+}
+
+/// すでにウィンドウが開いているときに起動した場合の動作（--instance / 設定ファイルの instance）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceMode {
+    /// 別のウィンドウを開く（従来の動作）
+    #[default]
+    New,
+    /// 実行中のウィンドウのキューに追加して終了する
+    Queue,
+    /// 実行中のウィンドウの作業を置き換えて終了する
+    Replace,
+}
+
+impl InstanceMode {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "new" => Ok(InstanceMode::New),
+            "queue" => Ok(InstanceMode::Queue),
+            "replace" => Ok(InstanceMode::Replace),
+            _ => Err(tr!("instance_invalid", value = s)),
+        }
+    }
+}
+
+/// 実行中の作業の状態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Status {
+    pub name: String,
+    pub start: DateTime<Local>,
+    /// 一時停止していた時間を除いた経過時間[秒]
+    pub elapsed_secs: u64,
+    pub estimate_minutes: Option<u64>,
+    pub deadline: Option<DateTime<Local>>,
+    /// 〆までの残り[秒]（超過していれば負）
+    pub remaining_secs: Option<i64>,
+    pub paused: bool,
+    pub memo: String,
+    pub checklist: Vec<ChecklistItem>,
+    /// キューに残っている作業の名前
+    pub queue: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 処理した後の状態
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+}
+
+impl Response {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            status: None,
+        }
+    }
+}
+
+/// この環境で制御 API を使えるか（Unix ドメインソケットを使うので Unix のみ）
+pub const AVAILABLE: bool = cfg!(unix);
+
+/// GUI スレッドへ渡すリクエストと、応答の返し先
+pub type Incoming = (Request, mpsc::Sender<Response>);
+
+/// ソケットのパス（例: /run/user/1000/taskstopwatch/control.sock。XDG_RUNTIME_DIR がなければデータディレクトリ）
+pub fn socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::data_dir)
+        .map(|d| d.join("taskstopwatch").join("control.sock"))
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // GUI スレッドの応答を待つ時間
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(3);
+
+    /// ソケットで待ち受けている間存在し、破棄されるとソケットファイルを消す
+    pub struct Server {
+        path: PathBuf,
+    }
+
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    /// 待ち受けを始める。受け取ったリクエストは tx に送り、wake で GUI を起こす。
+    pub fn serve(
+        tx: mpsc::Sender<Incoming>,
+        wake: impl Fn() + Send + Sync + 'static,
+    ) -> Result<Server, String> {
+        let path = socket_path().ok_or(tr!("socket_path_missing"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| tr!("dir_create_failed", path = dir.display(), error = e))?;
+        }
+        // 応答するソケットがあれば別のウィンドウが使っている。応答しなければ前回の残りなので消す。
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(tr!("socket_in_use", path = path.display()));
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| tr!("socket_bind_failed", path = path.display(), error = e))?;
+        // 応答の遅いクライアントがほかの接続を待たせないよう、接続ごとにスレッドで処理する
+        let wake = Arc::new(wake);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (tx, wake) = (tx.clone(), Arc::clone(&wake));
+                std::thread::spawn(move || handle(stream, &tx, &*wake));
+            }
+        });
+        Ok(Server { path })
+    }
+
+    fn handle(stream: UnixStream, tx: &mpsc::Sender<Incoming>, wake: &impl Fn()) {
+        let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+        let mut reader = BufReader::new(&stream);
+        let mut line = String::new();
+        while matches!(reader.read_line(&mut line), Ok(n) if n > 0) {
+            let response = match serde_json::from_str::<Request>(line.trim()) {
+                Ok(request) => {
+                    let (reply_tx, reply_rx) = mpsc::channel();
+                    if tx.send((request, reply_tx)).is_err() {
+                        return;
+                    }
+                    wake();
+                    reply_rx
+                        .recv_timeout(REPLY_TIMEOUT)
+                        .unwrap_or_else(|_| Response::error(tr!("control_no_reply")))
+                }
+                Err(e) => Response::error(tr!("control_bad_request", error = e)),
+            };
+            let Ok(json) = serde_json::to_string(&response) else {
+                return;
+            };
+            if writeln!(&stream, "{}", json).is_err() {
+                return;
+            }
+            line.clear();
+        }
+    }
+
+    /// 実行中のウィンドウにリクエストを送って応答を受け取る
+    pub fn send(request: &Request) -> Result<Response, String> {
+        let path = socket_path().ok_or(tr!("socket_path_missing"))?;
+        send_to(&path, request)
+    }
+
+    fn send_to(path: &Path, request: &Request) -> Result<Response, String> {
+        let stream = UnixStream::connect(path)
+            .map_err(|e| tr!("control_connect_failed", path = path.display(), error = e))?;
+        let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT * 2));
+        let json = serde_json::to_string(request).map_err(|e| e.to_string())?;
+        writeln!(&stream, "{}", json).map_err(|e| tr!("control_send_failed", error = e))?;
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .map_err(|e| tr!("control_receive_failed", error = e))?;
+        serde_json::from_str(line.trim()).map_err(|e| tr!("control_bad_response", error = e))
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::*;
+
+    pub struct Server;
+
+    pub fn serve(
+        _tx: mpsc::Sender<Incoming>,
+        _wake: impl Fn() + Send + Sync + 'static,
+    ) -> Result<Server, String> {
+        Err(tr!("control_unsupported").to_owned())
+    }
+
+    pub fn send(_request: &Request) -> Result<Response, String> {
+        Err(tr!("control_unsupported").to_owned())
+    }
+}
+
+pub use imp::{Server, send, serve};
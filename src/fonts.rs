@@ -0,0 +1,212 @@
+// 表示に使うフォント
+// 既定では日本語を同梱の Noto Sans JP（Windows ではメイリオ）で表示する。設定ファイルの [font] で、
+// フォントファイルやフォント名（Linux などでは fontconfig の fc-match / fc-list で探す）と文字の大きさを指定できる。
+// 足りない文字は次の順に探す: 指定したフォント → 日本語 → [font] の fallbacks → 中国語（簡体字・繁体字）・韓国語
+// （OS に入っているフォントを探す）→ egui の組み込みのフォント（絵文字を含む）。
+//
+//   [font]
+//   family = "Noto Sans CJK JP"
+//   size = 15.0
+use taskstopwatch::config::FontConfig;
+use taskstopwatch::tr;
+use eframe::egui::{self, FontData, FontDefinitions, FontFamily};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// 同梱のフォント
+const BUNDLED_JP: &[u8] = include_bytes!("../assets/NotoSansJP-VariableFont_wght.ttf");
+
+/// OS に入っているフォントのファイル
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FontFile {
+    path: PathBuf,
+    /// .ttc の中の番号
+    index: u32,
+}
+
+impl FontFile {
+    // ファイルは一度だけ読み込み、同じ .ttc の別の番号や設定の再読み込みでは読み込んだものを使い回す
+    // （読み込んだ内容はプロセスの終了まで残す）
+    fn load(&self) -> std::io::Result<FontData> {
+        static LOADED: OnceLock<Mutex<HashMap<PathBuf, &'static [u8]>>> = OnceLock::new();
+        let mut loaded = LOADED.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+        let bytes = match loaded.get(&self.path) {
+            Some(bytes) => *bytes,
+            None => {
+                let bytes: &'static [u8] = Box::leak(std::fs::read(&self.path)?.into_boxed_slice());
+                loaded.insert(self.path.clone(), bytes);
+                bytes
+            }
+        };
+        let mut data = FontData::from_static(bytes);
+        data.index = self.index;
+        Ok(data)
+    }
+}
+
+/// 設定からフォントを組み立てる（読めないフォントはメッセージを出して飛ばす）
+pub fn definitions(config: &FontConfig) -> FontDefinitions {
+    let mut chain: Vec<(String, FontData)> = Vec::new();
+    let mut used: Vec<FontFile> = Vec::new();
+    let mut add = |chain: &mut Vec<(String, FontData)>, file: FontFile| {
+        if used.contains(&file) {
+            return;
+        }
+        match file.load() {
+            Ok(data) => {
+                chain.push((format!("{}#{}", file.path.display(), file.index), data));
+                used.push(file);
+            }
+            Err(e) => eprintln!("{}", tr!("font_load_failed", font = file.path.display(), error = e)),
+        }
+    };
+
+    // 指定したフォント
+    if let Some(path) = &config.file {
+        add(&mut chain, FontFile { path: path.clone(), index: config.index });
+    } else if let Some(family) = &config.family {
+        match system::find(family) {
+            Some(file) => add(&mut chain, file),
+            None => eprintln!("{}", tr!("font_not_found", font = family)),
+        }
+    }
+    // 日本語（OS のフォントがなければ同梱のフォント）
+    match system::japanese() {
+        Some(file) => add(&mut chain, file),
+        None => chain.push(("noto_sans_jp".to_owned(), FontData::from_static(BUNDLED_JP))),
+    }
+    // 設定で足したフォントと、中国語・韓国語のフォント
+    for family in &config.fallbacks {
+        match system::find(family) {
+            Some(file) => add(&mut chain, file),
+            None => eprintln!("{}", tr!("font_not_found", font = family)),
+        }
+    }
+    // 中国語・韓国語のフォントは設定によらないので、探すのは最初の1回だけ
+    static FALLBACKS: OnceLock<Vec<FontFile>> = OnceLock::new();
+    for file in FALLBACKS.get_or_init(system::fallbacks) {
+        add(&mut chain, file.clone());
+    }
+
+    // 文章は組み立てた順に探し、最後に egui の組み込みのフォント（絵文字など）を使う。
+    // 等幅は egui の等幅フォントを先に使い、足りない文字を組み立てたフォントで補う
+    let mut fonts = FontDefinitions::default();
+    let names: Vec<String> = chain.iter().map(|(name, _)| name.clone()).collect();
+    for (name, data) in chain {
+        fonts.font_data.insert(name, data.into());
+    }
+    let proportional = fonts.families.entry(FontFamily::Proportional).or_default();
+    proportional.splice(0..0, names.iter().cloned());
+    fonts.families.entry(FontFamily::Monospace).or_default().extend(names);
+    fonts
+}
+
+/// ctx にフォントと文字の大きさを設定する（設定の再読み込みで [font] が変わったときにも呼ぶ）
+/// size を省略した場合は egui の既定の大きさに戻す
+pub fn install(ctx: &egui::Context, config: &FontConfig) {
+    ctx.set_fonts(definitions(config));
+    let defaults = egui::Style::default().text_styles;
+    ctx.style_mut(|s| {
+        for style in [egui::TextStyle::Body, egui::TextStyle::Button, egui::TextStyle::Monospace] {
+            if let Some(font) = s.text_styles.get_mut(&style) {
+                font.size = config.size.or_else(|| defaults.get(&style).map(|f| f.size)).unwrap_or(font.size);
+            }
+        }
+    });
+}
+
+// Windows: フォントフォルダの決まったファイルを使う（フォント名での指定はできない）
+#[cfg(target_os = "windows")]
+mod system {
+    use super::FontFile;
+    use std::path::PathBuf;
+
+    fn first_existing(candidates: &[&str]) -> Option<FontFile> {
+        let dir = std::env::var_os("WINDIR").map_or_else(|| PathBuf::from(r"C:\Windows"), PathBuf::from);
+        candidates
+            .iter()
+            .map(|name| dir.join("Fonts").join(name))
+            .find(|path| path.is_file())
+            .map(|path| FontFile { path, index: 0 })
+    }
+
+    pub fn find(_family: &str) -> Option<FontFile> {
+        None
+    }
+
+    /// メイリオ
+    pub fn japanese() -> Option<FontFile> {
+        first_existing(&["meiryo.ttc", "meiryo.ttf", "meiryob.ttc", "meiryob.ttf"])
+    }
+
+    /// Microsoft YaHei（簡体字）、Microsoft JhengHei（繁体字）、Malgun Gothic（韓国語）
+    pub fn fallbacks() -> Vec<FontFile> {
+        [&["msyh.ttc", "msyh.ttf"][..], &["msjh.ttc", "msjh.ttf"], &["malgun.ttf"]]
+            .iter()
+            .filter_map(|candidates| first_existing(candidates))
+            .collect()
+    }
+}
+
+// Linux など: fontconfig のコマンド（fc-match / fc-list）で探す。コマンドがなければ何も見つからない
+#[cfg(not(target_os = "windows"))]
+mod system {
+    use super::FontFile;
+    use std::process::Command;
+
+    const FORMAT: &str = "%{file}\t%{index}\n";
+
+    // fontconfig のコマンドを実行して、出力されたフォントのファイルを順に返す
+    fn query(command: &str, args: &[&str]) -> Vec<FontFile> {
+        let Ok(output) = Command::new(command).arg("-f").arg(FORMAT).args(args).output() else {
+            return Vec::new();
+        };
+        if !output.status.success() {
+            return Vec::new();
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (path, index) = line.split_once('\t')?;
+                Some(FontFile {
+                    path: path.into(),
+                    index: index.trim().parse().unwrap_or(0),
+                })
+            })
+            .filter(|f| !f.path.as_os_str().is_empty())
+            .collect()
+    }
+
+    // fc-match は合うものがなくても代わりのフォントを返すので、fc-list で条件に合うフォントの中から選ぶ
+    fn best(pattern: &str, filter: &str) -> Option<FontFile> {
+        let matching = query("fc-list", &[filter]);
+        if matching.is_empty() {
+            return None;
+        }
+        query("fc-match", &["-s", pattern])
+            .into_iter()
+            .find(|f| matching.contains(f))
+            .or_else(|| matching.into_iter().next())
+    }
+
+    /// フォント名で探す
+    pub fn find(family: &str) -> Option<FontFile> {
+        // fc-list の条件ではフォント名の中の "-" や ":" を区切りとして扱わないようにする
+        let escaped = family.replace('\\', "\\\\").replace('-', "\\-").replace(':', "\\:");
+        best(&escaped, &escaped)
+    }
+
+    /// 日本語は同梱のフォントを使う
+    pub fn japanese() -> Option<FontFile> {
+        None
+    }
+
+    /// 中国語（簡体字・繁体字）と韓国語のフォント
+    pub fn fallbacks() -> Vec<FontFile> {
+        ["zh-cn", "zh-tw", "ko"]
+            .iter()
+            .filter_map(|lang| best(&format!("sans-serif:lang={}", lang), &format!(":lang={}", lang)))
+            .collect()
+    }
+}
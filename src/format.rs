@@ -0,0 +1,131 @@
+// 時間・時刻の表示形式（設定ファイルの [format]）
+// ウィンドウ・端末の画面・クリップボードで、経過時間などの長さと〆などの時刻を同じ形式で表示する。
+//
+//   [format]
+//   duration = "auto"   # auto: 05:00 / 1:05:00, hms: 0:05:00, decimal: 1.08h, units: 1h 5m
+//   clock = "24h"       # 24h: 14:05, 12h: 2:05 PM
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// 時間の長さの表示形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationStyle {
+    /// mm:ss（1時間以上なら h:mm:ss）
+    #[default]
+    Auto,
+    /// 常に h:mm:ss
+    Hms,
+    /// 時間の小数（例: 1.08h）
+    Decimal,
+    /// 単位付き（例: 1h 5m、5m 30s）
+    Units,
+}
+
+/// 時刻の表示形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum ClockStyle {
+    #[default]
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "12h")]
+    H12,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Format {
+    pub duration: DurationStyle,
+    pub clock: ClockStyle,
+}
+
+impl Format {
+    /// 時間の長さ（経過時間・残り時間・所要時間など）
+    pub fn duration(&self, d: Duration) -> String {
+        let secs = d.as_secs();
+        let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+        match self.duration {
+            DurationStyle::Auto if h == 0 => format!("{:02}:{:02}", m, s),
+            DurationStyle::Auto | DurationStyle::Hms => format!("{}:{:02}:{:02}", h, m, s),
+            DurationStyle::Decimal => format!("{:.2}h", secs as f64 / 3600.0),
+            DurationStyle::Units => match (h, m) {
+                (0, 0) => format!("{}s", s),
+                (0, _) => format!("{}m {}s", m, s),
+                (_, 0) => format!("{}h", h),
+                _ => format!("{}h {}m", h, m),
+            },
+        }
+    }
+
+    /// 時刻（14:05 / 2:05 PM）
+    pub fn clock(&self, t: &DateTime<Local>) -> String {
+        match self.clock {
+            ClockStyle::H24 => t.format("%H:%M").to_string(),
+            ClockStyle::H12 => t.format("%-I:%M %p").to_string(),
+        }
+    }
+
+    /// 日付付きの時刻（12/24 14:05）
+    pub fn date_time(&self, t: &DateTime<Local>) -> String {
+        format!("{} {}", t.format("%m/%d"), self.clock(t))
+    }
+
+    /// 〆などの時刻: 今日なら時刻のみ、別の日なら日付も付ける
+    pub fn deadline(&self, t: &DateTime<Local>) -> String {
+        if t.date_naive() == Local::now().date_naive() {
+            self.clock(t)
+        } else {
+            self.date_time(t)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn durations(style: DurationStyle) -> Vec<String> {
+        let format = Format {
+            duration: style,
+            ..Format::default()
+        };
+        [0, 59, 300, 330, 3600, 3900, 36000 + 59]
+            .iter()
+            .map(|&s| format.duration(Duration::from_secs(s)))
+            .collect()
+    }
+
+    #[test]
+    fn duration_styles() {
+        assert_eq!(
+            durations(DurationStyle::Auto),
+            ["00:00", "00:59", "05:00", "05:30", "1:00:00", "1:05:00", "10:00:59"]
+        );
+        assert_eq!(
+            durations(DurationStyle::Hms),
+            ["0:00:00", "0:00:59", "0:05:00", "0:05:30", "1:00:00", "1:05:00", "10:00:59"]
+        );
+        assert_eq!(
+            durations(DurationStyle::Decimal),
+            ["0.00h", "0.02h", "0.08h", "0.09h", "1.00h", "1.08h", "10.02h"]
+        );
+        assert_eq!(
+            durations(DurationStyle::Units),
+            ["0s", "59s", "5m 0s", "5m 30s", "1h", "1h 5m", "10h"]
+        );
+    }
+
+    #[test]
+    fn clock_styles() {
+        let t = Local.with_ymd_and_hms(2025, 12, 24, 14, 5, 0).unwrap();
+        let h12 = Format {
+            clock: ClockStyle::H12,
+            ..Format::default()
+        };
+        assert_eq!(Format::default().clock(&t), "14:05");
+        assert_eq!(h12.clock(&t), "2:05 PM");
+        assert_eq!(h12.date_time(&t), "12/24 2:05 PM");
+    }
+}
@@ -0,0 +1,149 @@
+// ウィンドウの位置と大きさ
+// 作業名ごとに最後の位置と大きさをデータディレクトリに保存しておき、同じ作業名で起動したときに元に戻す。
+// --position を指定すると、ウィンドウを画面の隅（または中央）に置く。
+use crate::tr;
+use eframe::egui::{self, Pos2, Vec2, pos2};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// 画面の端からの余白
+const MARGIN: f32 = 16.0;
+
+/// 保存するウィンドウの位置（枠を含む左上）と、中身の大きさ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Geometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Geometry {
+    pub fn new(position: Pos2, size: Vec2) -> Self {
+        Self {
+            x: position.x,
+            y: position.y,
+            width: size.x,
+            height: size.y,
+        }
+    }
+
+    pub fn position(&self) -> Pos2 {
+        pos2(self.x, self.y)
+    }
+
+    pub fn size(&self) -> Vec2 {
+        egui::vec2(self.width, self.height)
+    }
+}
+
+/// --position で指定する置き場所
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl Corner {
+    /// "top-right" などを解析する
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "top-left" => Ok(Corner::TopLeft),
+            "top-right" => Ok(Corner::TopRight),
+            "bottom-left" => Ok(Corner::BottomLeft),
+            "bottom-right" => Ok(Corner::BottomRight),
+            "center" => Ok(Corner::Center),
+            _ => Err(tr!("position_invalid", value = s)),
+        }
+    }
+
+    /// 画面の大きさ monitor に、枠を含めて outer の大きさのウィンドウを置くときの左上の位置
+    pub fn position(self, monitor: Vec2, outer: Vec2) -> Pos2 {
+        let left = MARGIN;
+        let right = (monitor.x - outer.x - MARGIN).max(0.0);
+        let top = MARGIN;
+        let bottom = (monitor.y - outer.y - MARGIN).max(0.0);
+        match self {
+            Corner::TopLeft => pos2(left, top),
+            Corner::TopRight => pos2(right, top),
+            Corner::BottomLeft => pos2(left, bottom),
+            Corner::BottomRight => pos2(right, bottom),
+            Corner::Center => pos2(
+                ((monitor.x - outer.x) / 2.0).max(0.0),
+                ((monitor.y - outer.y) / 2.0).max(0.0),
+            ),
+        }
+    }
+}
+
+/// 保存先のパス（例: ~/.local/share/taskstopwatch/geometry.json）
+pub fn geometry_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("taskstopwatch").join("geometry.json"))
+}
+
+// 作業名 -> 位置と大きさ（無い・壊れている場合は空）
+fn load_all() -> BTreeMap<String, Geometry> {
+    geometry_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// 作業名に対して最後に保存した位置と大きさ
+pub fn load(name: &str) -> Option<Geometry> {
+    load_all().remove(name)
+}
+
+/// 作業名に対する位置と大きさを保存する（ほかの作業名の分はそのまま残す）
+pub fn save(name: &str, geometry: Geometry) -> std::io::Result<()> {
+    let path = geometry_path().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, tr!("data_dir_missing"))
+    })?;
+    let mut all = load_all();
+    if all.get(name) == Some(&geometry) {
+        return Ok(());
+    }
+    all.insert(name.to_owned(), geometry);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&all).map_err(std::io::Error::other)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        assert_eq!(Corner::parse(" top-right "), Ok(Corner::TopRight));
+        assert_eq!(Corner::parse("center"), Ok(Corner::Center));
+        assert!(Corner::parse("top").is_err());
+    }
+
+    #[test]
+    fn position_keeps_a_margin() {
+        let monitor = egui::vec2(1920.0, 1080.0);
+        let outer = egui::vec2(300.0, 200.0);
+        assert_eq!(Corner::TopLeft.position(monitor, outer), pos2(16.0, 16.0));
+        assert_eq!(Corner::TopRight.position(monitor, outer), pos2(1604.0, 16.0));
+        assert_eq!(Corner::BottomLeft.position(monitor, outer), pos2(16.0, 864.0));
+        assert_eq!(Corner::BottomRight.position(monitor, outer), pos2(1604.0, 864.0));
+        assert_eq!(Corner::Center.position(monitor, outer), pos2(810.0, 440.0));
+    }
+
+    #[test]
+    fn position_stays_on_screen_for_large_windows() {
+        let monitor = egui::vec2(800.0, 600.0);
+        let outer = egui::vec2(1000.0, 700.0);
+        assert_eq!(Corner::BottomRight.position(monitor, outer), pos2(0.0, 0.0));
+        assert_eq!(Corner::Center.position(monitor, outer), pos2(0.0, 0.0));
+    }
+}
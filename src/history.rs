@@ -0,0 +1,150 @@
+// セッション履歴の保存
+// 1セッション = 1行の JSON (JSON Lines) として XDG データディレクトリに追記する。
+use crate::pause::PauseInterval;
+use crate::pomodoro::PhaseRecord;
+use crate::tr;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// メモ中のチェックボックス1項目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub text: String,
+    pub checked: bool,
+    /// 直前のチェックからこの項目をチェックするまでの時間[秒]
+    #[serde(default)]
+    pub split_secs: Option<u64>,
+}
+
+/// 履歴ファイルに書き出す1セッション分の記録
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub name: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    /// 一時停止していた時間を除いた正味の作業時間[秒]
+    pub elapsed_secs: u64,
+    /// 第3引数で渡された所要見積時間[分]
+    pub estimate_minutes: Option<u64>,
+    /// 〆の時刻（見積時間または --until から求めたもの）
+    #[serde(default)]
+    pub deadline: Option<DateTime<Local>>,
+    pub memo: String,
+    pub checklist: Vec<ChecklistItem>,
+    /// 一時停止していた時間の合計[秒]と各区間
+    #[serde(default)]
+    pub paused_secs: u64,
+    #[serde(default)]
+    pub pauses: Vec<PauseInterval>,
+    /// ポモドーロモードで実行した場合の作業・休憩の区間
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<PhaseRecord>,
+    /// 完了ボタンで終了した場合は true、ウィンドウを閉じた場合は false
+    pub completed: bool,
+}
+
+/// 履歴ファイルのパス（例: ~/.local/share/taskstopwatch/sessions.jsonl）
+pub fn history_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("taskstopwatch").join("sessions.jsonl"))
+}
+
+/// メモから `- [ ]` / `- [x]` 行（入れ子を含む）を取り出す
+pub fn checklist_from_memo(memo: &str) -> Vec<ChecklistItem> {
+    memo.lines()
+        .filter_map(crate::markdown::task_item)
+        .map(|(checked, text)| ChecklistItem {
+            text: text.to_owned(),
+            checked,
+            split_secs: None,
+        })
+        .collect()
+}
+
+/// 記録を履歴ファイルの末尾に1行追記する
+pub fn append(record: &SessionRecord) -> std::io::Result<PathBuf> {
+    let path = history_path().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, tr!("data_dir_missing"))
+    })?;
+    append_to(&path, record)?;
+    Ok(path)
+}
+
+// 指定したファイルの末尾に1行追記する
+fn append_to(path: &Path, record: &SessionRecord) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(name: &str) -> SessionRecord {
+        let start = Local.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let memo = "- [x] 資料を読む\n- [ ] コメントを書く".to_owned();
+        let mut checklist = checklist_from_memo(&memo);
+        checklist[0].split_secs = Some(300);
+        SessionRecord {
+            name: name.to_owned(),
+            start,
+            end: start + chrono::Duration::minutes(30),
+            elapsed_secs: 1500,
+            estimate_minutes: Some(25),
+            deadline: Some(start + chrono::Duration::minutes(30)),
+            memo,
+            checklist,
+            paused_secs: 300,
+            pauses: vec![PauseInterval {
+                start: start + chrono::Duration::minutes(10),
+                end: start + chrono::Duration::minutes(15),
+                secs: 300,
+            }],
+            phases: Vec::new(),
+            completed: true,
+        }
+    }
+
+    fn json(record: &SessionRecord) -> serde_json::Value {
+        serde_json::to_value(record).unwrap()
+    }
+
+    #[test]
+    fn checklist_includes_nested_items() {
+        let items = checklist_from_memo("# 見出し\n- [ ] a\n  * [X] b\n- c");
+        let items: Vec<_> = items.iter().map(|c| (c.text.as_str(), c.checked)).collect();
+        assert_eq!(items, [("a", false), ("b", true)]);
+    }
+
+    #[test]
+    fn record_round_trips_through_json() {
+        let original = record("作業");
+        let line = serde_json::to_string(&original).unwrap();
+        assert!(!line.contains('\n'));
+        let parsed: SessionRecord = serde_json::from_str(&line).unwrap();
+        assert_eq!(json(&parsed), json(&original));
+    }
+
+    #[test]
+    fn append_adds_one_line_per_record() {
+        let dir = std::env::temp_dir().join(format!("taskstopwatch-history-{}", std::process::id()));
+        let path = dir.join("sessions.jsonl");
+        let _ = std::fs::remove_file(&path);
+        append_to(&path, &record("1")).unwrap();
+        append_to(&path, &record("2")).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let names: Vec<String> = text
+            .lines()
+            .map(|line| serde_json::from_str::<SessionRecord>(line).unwrap().name)
+            .collect();
+        assert_eq!(names, ["1", "2"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,252 @@
+// フック（イベントごとにユーザーのコマンドを実行する）
+// 設定ファイルの [[hooks]] で、開始・見積時間の N%・〆・超過 N 分ごと・チェック・完了のときに
+// 実行するコマンドを指定する。セッションの情報は TSW_ で始まる環境変数で渡す。
+//
+//   [[hooks]]
+//   event = "progress"
+//   percent = 80
+//   command = "notify-send \"$TSW_NAME\" \"残り $TSW_REMAINING_SECS 秒\""
+use crate::history::SessionRecord;
+use crate::tr;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// 作業の開始（キューの次の作業を含む）
+    Start,
+    /// 見積時間（〆まで）の percent % が経過した
+    Progress,
+    /// 〆になった
+    Deadline,
+    /// 〆を過ぎてから every_minutes 分ごと
+    Overtime,
+    /// チェックリストの項目にチェックを付けた
+    Tick,
+    /// 「完了!」を押した
+    Done,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::Start => "start",
+            HookEvent::Progress => "progress",
+            HookEvent::Deadline => "deadline",
+            HookEvent::Overtime => "overtime",
+            HookEvent::Tick => "tick",
+            HookEvent::Done => "done",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    pub event: HookEvent,
+    /// シェル（Windows では cmd /C）で実行するコマンド
+    pub command: String,
+    /// progress のときの割合[%]
+    #[serde(default)]
+    pub percent: Option<u32>,
+    /// overtime のときの間隔[分]
+    #[serde(default)]
+    pub every_minutes: Option<u64>,
+}
+
+impl Hook {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.command.trim().is_empty() {
+            return Err(tr!("hook_command_empty").to_owned());
+        }
+        if self.event == HookEvent::Progress && !self.percent.is_some_and(|p| (1..=1000).contains(&p)) {
+            return Err(tr!("hook_percent_required").to_owned());
+        }
+        if self.event == HookEvent::Overtime && self.every_minutes.is_none_or(|m| m == 0) {
+            return Err(tr!("hook_every_required").to_owned());
+        }
+        if self.percent.is_some() && self.event != HookEvent::Progress {
+            return Err(tr!("hook_percent_only", event = self.event.name()));
+        }
+        if self.every_minutes.is_some() && self.event != HookEvent::Overtime {
+            return Err(tr!("hook_every_only", event = self.event.name()));
+        }
+        Ok(())
+    }
+}
+
+/// 時間で起きるイベント（progress / deadline / overtime）を一度だけ実行するための記録
+#[derive(Debug, Default)]
+pub struct HookTracker {
+    started: bool,
+    done: bool,
+    // フックの番号 -> 実行済みの回数（overtime は何回目の間隔まで実行したか）
+    fired: BTreeMap<usize, u64>,
+}
+
+impl HookTracker {
+    /// 最初の1回だけ true（start フックの実行用）
+    pub fn take_start(&mut self) -> bool {
+        !std::mem::replace(&mut self.started, true)
+    }
+
+    /// 最初の1回だけ true（done フックの実行用。クリップボードへのコピーをやり直しても再び実行しない）
+    pub fn take_done(&mut self) -> bool {
+        !std::mem::replace(&mut self.done, true)
+    }
+
+    /// 設定の再読み込みや中断セッションの再開のときに、既に過ぎたイベントを実行済みにする
+    pub fn reset(&mut self, hooks: &[Hook], elapsed: u64, remaining: Option<i64>) {
+        self.fired.clear();
+        self.due(hooks, elapsed, remaining);
+    }
+
+    /// elapsed[秒] と〆までの残り[秒]から、新たに実行すべきフックの番号を返す
+    pub fn due(&mut self, hooks: &[Hook], elapsed: u64, remaining: Option<i64>) -> Vec<usize> {
+        let Some(remaining) = remaining else {
+            return Vec::new();
+        };
+        // 〆までの全体の長さ（--until の場合も経過 + 残りで求める）
+        let total = elapsed as i64 + remaining;
+        let mut due = Vec::new();
+        for (i, hook) in hooks.iter().enumerate() {
+            let count = match hook.event {
+                HookEvent::Progress => {
+                    let percent = hook.percent.unwrap_or(100) as i64;
+                    u64::from(total > 0 && elapsed as i64 * 100 >= total * percent)
+                }
+                HookEvent::Deadline => u64::from(remaining <= 0),
+                HookEvent::Overtime => {
+                    let every = hook.every_minutes.unwrap_or(1).max(1) * 60;
+                    if remaining < 0 { remaining.unsigned_abs() / every } else { 0 }
+                }
+                _ => continue,
+            };
+            let fired = self.fired.entry(i).or_insert(0);
+            if count > *fired {
+                *fired = count;
+                due.push(i);
+            }
+        }
+        due
+    }
+}
+
+/// セッションの情報を環境変数にする
+pub fn env_vars(event: HookEvent, record: &SessionRecord, remaining: Option<i64>) -> Vec<(String, String)> {
+    let opt = |v: Option<String>| v.unwrap_or_default();
+    let checked = record.checklist.iter().filter(|c| c.checked).count();
+    [
+        ("TSW_EVENT", event.name().to_owned()),
+        ("TSW_NAME", record.name.clone()),
+        ("TSW_MEMO", record.memo.clone()),
+        ("TSW_START", record.start.to_rfc3339()),
+        ("TSW_ELAPSED_SECS", record.elapsed_secs.to_string()),
+        ("TSW_ESTIMATE_MINUTES", opt(record.estimate_minutes.map(|m| m.to_string()))),
+        ("TSW_DEADLINE", opt(record.deadline.map(|d| d.to_rfc3339()))),
+        ("TSW_REMAINING_SECS", opt(remaining.map(|r| r.to_string()))),
+        ("TSW_CHECKED", checked.to_string()),
+        ("TSW_TOTAL", record.checklist.len().to_string()),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_owned(), v))
+    .collect()
+}
+
+/// フックのコマンドを実行する（終了は待たない）
+pub fn run(hook: &Hook, vars: &[(String, String)]) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(&hook.command);
+        c
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut command = {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(&hook.command);
+        c
+    };
+    let mut child = command
+        .envs(vars.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| tr!("hook_failed", event = hook.event.name(), error = e))?;
+    // 終了したプロセスが残らないよう別スレッドで待つ
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(event: HookEvent, percent: Option<u32>, every_minutes: Option<u64>) -> Hook {
+        Hook {
+            event,
+            command: "true".to_owned(),
+            percent,
+            every_minutes,
+        }
+    }
+
+    fn hooks() -> Vec<Hook> {
+        vec![
+            hook(HookEvent::Start, None, None),
+            hook(HookEvent::Progress, Some(50), None),
+            hook(HookEvent::Deadline, None, None),
+            hook(HookEvent::Overtime, None, Some(5)),
+        ]
+    }
+
+    #[test]
+    fn due_fires_each_event_once() {
+        let hooks = hooks();
+        let mut tracker = HookTracker::default();
+        // 見積 10 分
+        assert!(tracker.due(&hooks, 0, Some(600)).is_empty());
+        assert_eq!(tracker.due(&hooks, 300, Some(300)), [1]);
+        assert!(tracker.due(&hooks, 301, Some(299)).is_empty());
+        assert_eq!(tracker.due(&hooks, 600, Some(0)), [2]);
+        assert!(tracker.due(&hooks, 899, Some(-299)).is_empty());
+        assert_eq!(tracker.due(&hooks, 900, Some(-300)), [3]);
+        assert!(tracker.due(&hooks, 1000, Some(-400)).is_empty());
+        assert_eq!(tracker.due(&hooks, 1200, Some(-600)), [3]);
+    }
+
+    #[test]
+    fn due_without_deadline_fires_nothing() {
+        let mut tracker = HookTracker::default();
+        assert!(tracker.due(&hooks(), 100_000, None).is_empty());
+    }
+
+    #[test]
+    fn due_catches_up_in_one_call() {
+        let mut tracker = HookTracker::default();
+        assert_eq!(tracker.due(&hooks(), 1200, Some(-600)), [1, 2, 3]);
+    }
+
+    #[test]
+    fn reset_skips_past_events() {
+        let hooks = hooks();
+        let mut tracker = HookTracker::default();
+        tracker.reset(&hooks, 400, Some(200));
+        assert!(tracker.due(&hooks, 500, Some(100)).is_empty());
+        // 〆を延ばして割合が下がってから再び超えたら実行する
+        tracker.reset(&hooks, 500, Some(1500));
+        assert_eq!(tracker.due(&hooks, 1000, Some(1000)), [1]);
+    }
+
+    #[test]
+    fn take_start_and_done_only_once() {
+        let mut tracker = HookTracker::default();
+        assert!(tracker.take_start());
+        assert!(!tracker.take_start());
+        assert!(tracker.take_done());
+        assert!(!tracker.take_done());
+    }
+}
@@ -0,0 +1,172 @@
+// 画面に表示する文字列の翻訳
+// 文字列は言語ごとのカタログ（locales/<言語>.toml、キー = 文字列）に置き、tr! でキーから引く。
+// 言語は設定ファイルの language で指定し、なければ環境変数 LC_ALL / LC_MESSAGES / LANG から決める。
+// カタログにないキーは日本語のカタログから引く。
+//
+//   tr!("done")                          // "完了!" / "Done!"
+//   tr!("elapsed", time = "05:00")       // "05:00 経過" / "05:00 elapsed"
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    Ja,
+    En,
+}
+
+// 組み込みのカタログ（Language の順）
+const CATALOGS: [&str; 2] = [
+    include_str!("../locales/ja.toml"),
+    include_str!("../locales/en.toml"),
+];
+
+// 現在の言語（UNSET の間は環境変数から決める）
+const UNSET: u8 = u8::MAX;
+static CURRENT: AtomicU8 = AtomicU8::new(UNSET);
+
+impl Language {
+    /// 環境変数から言語を決める（未設定や C ロケールなら従来どおり日本語）
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|k| std::env::var(k).ok())
+            .find(|v| !v.is_empty())
+            .unwrap_or_default();
+        Self::from_locale(&locale)
+    }
+
+    // "ja_JP.UTF-8" や "C.UTF-8" のようなロケール名から言語を決める（文字コードと @ 以降は見ない）
+    fn from_locale(locale: &str) -> Self {
+        let name = locale.split(['.', '@']).next().unwrap_or_default();
+        if name.is_empty() || name == "C" || name == "POSIX" || name.starts_with("ja") {
+            Language::Ja
+        } else {
+            Language::En
+        }
+    }
+}
+
+/// 表示する言語を切り替える（設定ファイルの読み込み時に呼ぶ）
+pub fn set_language(language: Option<Language>) {
+    let language = language.unwrap_or_else(Language::detect);
+    CURRENT.store(language as u8, Ordering::Relaxed);
+}
+
+/// 現在の言語
+pub fn language() -> Language {
+    match CURRENT.load(Ordering::Relaxed) {
+        0 => Language::Ja,
+        1 => Language::En,
+        _ => {
+            let detected = Language::detect();
+            CURRENT.store(detected as u8, Ordering::Relaxed);
+            detected
+        }
+    }
+}
+
+fn catalog(language: Language) -> &'static HashMap<String, String> {
+    static PARSED: OnceLock<Vec<HashMap<String, String>>> = OnceLock::new();
+    let parsed = PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|src| toml::from_str(src).expect("組み込みの翻訳カタログは常に解釈できる"))
+            .collect()
+    });
+    &parsed[language as usize]
+}
+
+/// キーに対応する現在の言語の文字列（どのカタログにもなければキーそのもの）
+pub fn text(key: &'static str) -> &'static str {
+    catalog(language())
+        .get(key)
+        .or_else(|| catalog(Language::Ja).get(key))
+        .map_or(key, String::as_str)
+}
+
+/// 文字列中の {名前} を値で置き換える（値の中の波かっこはそのまま残す）
+pub fn fill(text: &str, args: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after
+            .find('}')
+            .and_then(|close| args.iter().find(|(name, _)| *name == &after[..close]).map(|(_, v)| (close, v)));
+        match value {
+            Some((close, v)) => {
+                out.push_str(v);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 翻訳した文字列を返す。`名前 = 値` を渡すとカタログの {名前} を値で置き換えた String になる。
+#[macro_export]
+macro_rules! tr {
+    ($key:literal) => {
+        $crate::i18n::text($key)
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::text($key),
+            &[$((stringify!($name), ($value).to_string())),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[(&'static str, &str)]) -> Vec<(&'static str, String)> {
+        list.iter().map(|&(name, value)| (name, value.to_owned())).collect()
+    }
+
+    #[test]
+    fn fill_replaces_named_placeholders() {
+        let filled = fill("{name} まで {time}（{name}）", &args(&[("name", "作業"), ("time", "05:00")]));
+        assert_eq!(filled, "作業 まで 05:00（作業）");
+    }
+
+    #[test]
+    fn fill_keeps_unknown_and_unclosed_braces() {
+        assert_eq!(fill("{other} {x", &args(&[("x", "1")])), "{other} {x");
+        assert_eq!(fill("{{field}}", &args(&[("field", "name")])), "{name}");
+    }
+
+    #[test]
+    fn fill_does_not_expand_values() {
+        assert_eq!(fill("{a}{b}", &args(&[("a", "{b}"), ("b", "2")])), "{b}2");
+    }
+
+    #[test]
+    fn detect_ignores_codeset_and_modifier() {
+        for locale in ["", "C", "C.UTF-8", "POSIX", "ja_JP.UTF-8", "ja_JP.eucJP@euro", "ja"] {
+            assert_eq!(Language::from_locale(locale), Language::Ja, "{:?}", locale);
+        }
+        for locale in ["en_US.UTF-8", "de_DE@euro", "fr"] {
+            assert_eq!(Language::from_locale(locale), Language::En, "{:?}", locale);
+        }
+    }
+
+    #[test]
+    fn catalogs_have_the_same_keys() {
+        let mut ja: Vec<_> = catalog(Language::Ja).keys().collect();
+        let mut en: Vec<_> = catalog(Language::En).keys().collect();
+        ja.sort();
+        en.sort();
+        assert_eq!(ja, en);
+    }
+}
@@ -0,0 +1,46 @@
+//! 作業時間の計測/カウントダウン（TaskStopWatch）のライブラリ
+//!
+//! 作業セッションのモデル（[`session::Session`]: 開始・一時停止・〆・メモとチェックリスト・完了）と、
+//! それを任意の egui の `Ui` に表示する [`widget::StopwatchWidget`] を提供する。
+//! taskstopwatch コマンドもこのライブラリの上に作られている。
+//! 履歴・スナップショットの保存、フック、クリップボードへのコピーは既定では行わない
+//! （[`session::Session::persistence`] などで有効にする）。
+//!
+//! ```no_run
+//! use eframe::egui;
+//! use taskstopwatch::{config::Config, session::Session, widget::StopwatchWidget};
+//!
+//! let session = Session::new(
+//!     "設計レビュー".to_owned(),
+//!     "- [ ] 資料を読む\n- [ ] コメントを書く".to_owned(),
+//!     Some(30),
+//!     None,
+//!     Default::default(),
+//!     Config::default(),
+//! );
+//! let mut widget = StopwatchWidget::new(session);
+//! # let ctx = egui::Context::default();
+//! egui::Window::new("タイマー").show(&ctx, |ui| {
+//!     let output = widget.show(ui);
+//!     if output.finished {
+//!         // 完了してキューも空になった
+//!     }
+//! });
+//! ```
+pub mod config;
+pub mod control;
+pub mod format;
+pub mod geometry;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod markdown;
+pub mod memo_file;
+pub mod pause;
+pub mod pomodoro;
+pub mod queue;
+pub mod recovery;
+pub mod session;
+pub mod splits;
+pub mod template;
+pub mod widget;
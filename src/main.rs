@@ -1,477 +1,481 @@
 # ![windows_subsystem = "windows"]
 use eframe::egui;
-use egui::FontFamily;
-use egui::{RichText, Color32};
-use chrono::Local;
-use chrono::Duration as ChronoDuration;
+use std::collections::VecDeque;
 use std::time::{Duration, Instant};
-use arboard::Clipboard;
-use egui::Sense;
-use egui::Key;
+use taskstopwatch::widget::StopwatchWidget;
+use taskstopwatch::{config, control, geometry, i18n, memo_file, queue, recovery, session, tr};
+
+mod cli;
+mod fonts;
+mod tui;
 
 fn main() -> eframe::Result<()> {
+    // コマンドライン引数の解析（誤りがあればメッセージを出して終了する）
+    let cli = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Run(cli)) => cli,
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::usage());
+            return Ok(());
+        }
+        Ok(cli::Command::Version) => {
+            println!("taskstopwatch {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Ok(cli::Command::Ctl(request)) => run_ctl(request),
+        Err(e) => {
+            eprintln!("{}", tr!("error", error = e));
+            eprintln!("{}", tr!("see_help"));
+            std::process::exit(2);
+        }
+    };
+    // 設定の検証エラーは起動時に報告して終了する
+    let (config, config_path) = match config::load_startup(cli.config.as_deref()) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    i18n::set_language(config.language);
+    // `--clipboard <プリセット名|テンプレート>` で完了時のクリップボード出力を選ぶ
+    let clipboard_arg = cli.clipboard.clone();
+    if let Err(e) = config.clipboard.resolve(clipboard_arg.as_deref()) {
+        eprintln!("--clipboard: {}", e);
+        std::process::exit(2);
+    }
+
+    // `--queue <file>` が指定されたらキューファイルから作業を順に読み込む
+    let mut queue: VecDeque<queue::QueuedTask> = VecDeque::new();
+    if let Some(path) = &cli.queue {
+        match queue::load(path) {
+            Ok(tasks) => queue.extend(tasks),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    // 作業名・メモ・見積時間が指定されていればそれを最初に、なければキューの先頭から始める
+    // （--until だけを指定した場合はキューの先頭の作業の〆にする）
+    let first_task = if cli.queue.is_some()
+        && cli.name.is_none()
+        && cli.memo.is_none()
+        && cli.memo_file.is_none()
+        && cli.estimate.is_none()
+    {
+        queue.pop_front()
+    } else {
+        None
+    };
+
     // 引数から作業名を取得（なければデフォルト）
-    let task_name = std::env::args().nth(1).unwrap_or_else(|| "作業".to_owned());
+    let task_name = match &first_task {
+        Some(t) => t.name.clone(),
+        None => cli.name.clone().unwrap_or_else(|| tr!("default_name").to_owned()),
+    };
     // 第二引数をメモのデフォルト表示に使う（なければMarkdownでの太字表示を示すトークン）
     // HTML の <br> を改行に置換する
-    let memo_default_raw = std::env::args().nth(2).unwrap_or_else(|| "（memo）".to_owned());
-    let memo_default = memo_default_raw.replace("<br/>", "\n").replace("<br>", "\n");
+    let memo_default_raw = match &first_task {
+        Some(t) => t.memo.clone(),
+        None => cli.memo.clone().unwrap_or_else(|| tr!("default_memo").to_owned()),
+    };
+    let mut memo_default = memo_default_raw.replace("<br/>", "\n").replace("<br>", "\n");
+    // --memo-file（キューの場合は memo_file）が指定されていればファイルの内容をメモにする
+    let memo_file_path = match &first_task {
+        Some(t) => t.memo_file.clone(),
+        None => cli.memo_file.clone(),
+    };
+    let mut memo_file = None;
+    if let Some(path) = &memo_file_path {
+        match memo_file::MemoFile::open(path) {
+            Ok((file, memo)) => {
+                memo_file = Some(file);
+                memo_default = memo;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+    }
 
-    // ウィンドウの設定: memo の内容に合わせて初期サイズを計算する
-    // 最大行長と行数を正しく計算
+    // ウィンドウの設定: 最初の表示までの仮の大きさをメモの行数から見積もる
+    // （window.auto_size なら、表示したあとで実際の表示内容に合わせ直す）
     let memo_lines_count = memo_default.lines().count().max(1);
-    // let max_chars = memo_default
-    //     .lines()
-    //     .map(|l| l.chars().count())
-    //     .max()
-    //     .unwrap_or(20usize)
-    //     .max(20);
-    // 単純な推定: 1文字あたり約8px、1行あたり20px として計算
-    // let width = (200.0 + (max_chars as f32) * 8.0).clamp(200.0, 1200.0);
-    let width = 300.0_f32;
-    // 高さは固定要素分 + メモ行分 + ボタンが隠れない余白を確保
-    let fixed_ui_height = 140.0; // ヘッダ、タイマー、余白など
-    let memo_area = (memo_lines_count as f32) * 22.0; // 1行あたりの高さ
-    let button_pad = 80.0; // 完了ボタンと余白のためのスペース
-    let height = (fixed_ui_height + memo_area + button_pad).clamp(140.0, 1400.0);
+    let width = config.window.width;
+    // 高さは固定要素分 + メモ行分 + ボタンが隠れない余白を確保（値は設定ファイルで変更可）
+    let height = config.window.height_for(memo_lines_count);
+    // 同じ作業名で前回使った位置と大きさがあれば戻す（--position を指定したときは大きさだけ）
+    let saved_geometry = geometry::load(&task_name);
+    let corner = cli.position;
+    // 最前面に表示するか（--pin / --no-pin が設定ファイルより優先）
+    let pinned = cli.pin.unwrap_or(config.window.always_on_top);
+    // 通常の表示とミニバーの大きさ（切り替えるときにそれぞれの大きさを覚えておく）
+    let full_size = saved_geometry.map_or(egui::vec2(width, height), |g| g.size());
+    let compact_size = egui::vec2(config.window.compact_width, config.window.compact_height);
+    let compact = cli.compact;
+    let overlay = cli.overlay;
+    let mut viewport = egui::viewport::ViewportBuilder::default()
+            .with_inner_size(if compact { compact_size } else { full_size })
+            .with_decorations(!compact)
+            // 背景の不透明度を変えられるよう、ウィンドウ自体は透過させる（背景はウィジェットが塗る）
+            .with_transparent(true)
+            .with_mouse_passthrough(overlay)
+            .with_window_level(window_level(pinned));
+    if let Some(g) = saved_geometry
+        && corner.is_none()
+    {
+        viewport = viewport.with_position(g.position());
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::viewport::ViewportBuilder::default()
-            .with_inner_size([width, height]),
+        viewport,
         ..Default::default()
     };
-    // 第三引数は分数（整数）で受け取る（オプション）
-    let end_minutes = std::env::args().nth(3).and_then(|s| s.parse::<u64>().ok());
+    // 第三引数（--estimate）は分数で受け取る（オプション）
+    // キューの先頭から始める場合、--until を指定していればその作業の見積時間・〆の代わりに使う
+    let end_minutes = match &first_task {
+        Some(_) if cli.until.is_some() => None,
+        Some(t) => t.estimate,
+        None => cli.estimate,
+    };
+    // --until で指定された〆の時刻（キューの場合は until）
+    let until = match &first_task {
+        Some(t) => cli.until.or(t.until),
+        None => cli.until,
+    };
+    // --pomodoro または設定ファイルの pomodoro.enabled でポモドーロモードにする
+    let pomodoro = cli.pomodoro || config.pomodoro.enabled;
+
+    // すでにウィンドウが開いていれば、作業をそちらに渡して終了する（--instance queue / replace）
+    // 作業を指定せずに起動した場合は渡すものがないので、設定ファイルの instance によらず別に開く
+    let has_task = cli.name.is_some()
+        || cli.memo.is_some()
+        || cli.memo_file.is_some()
+        || cli.estimate.is_some()
+        || cli.until.is_some()
+        || cli.queue.is_some();
+    let instance = match cli.instance {
+        Some(mode) if mode != control::InstanceMode::New && !has_task => {
+            eprintln!("{}", tr!("error", error = tr!("instance_no_task")));
+            eprintln!("{}", tr!("see_help"));
+            std::process::exit(2);
+        }
+        Some(mode) => mode,
+        None if has_task => config.instance,
+        None => control::InstanceMode::New,
+    };
+    if instance != control::InstanceMode::New {
+        // 制御 API がなければ実行中のウィンドウに渡せず、黙って別のウィンドウを開くことになる
+        if !control::AVAILABLE {
+            eprintln!("{}", tr!("instance_unsupported"));
+            std::process::exit(2);
+        }
+        let mut tasks = vec![queue::QueuedTask {
+            name: task_name.clone(),
+            memo: memo_default.clone(),
+            estimate: end_minutes,
+            // 実行中のウィンドウとは作業ディレクトリが違うので絶対パスにする
+            memo_file: memo_file_path.as_deref().and_then(|p| std::path::absolute(p).ok()),
+            until,
+        }];
+        tasks.extend(queue.iter().cloned());
+        let request = control::Request::Open {
+            tasks,
+            replace: instance == control::InstanceMode::Replace,
+        };
+        // 接続できなければ開いているウィンドウはないので、このまま起動する
+        match control::send(&request) {
+            Ok(response) if response.ok => {
+                if instance == control::InstanceMode::Replace {
+                    println!("{}", tr!("instance_started", name = task_name));
+                } else {
+                    println!("{}", tr!("instance_queued", name = task_name));
+                }
+                return Ok(());
+            }
+            Ok(response) => {
+                eprintln!("{}", tr!("error", error = response.error.unwrap_or_default()));
+                std::process::exit(1);
+            }
+            Err(_) => {}
+        }
+    }
+
+    let mut session = session::Session::new(task_name, memo_default, end_minutes, until, queue, config.clone());
+    session.config_watcher = config_path.map(config::ConfigWatcher::new);
+    session.clipboard_arg = clipboard_arg;
+    session.memo_file = memo_file;
+    session.pomodoro = pomodoro.then_some(Duration::ZERO);
+    // 履歴・スナップショットの保存、フック、クリップボードを使う
+    session.persistence = Some(session::Persistence::open());
+    session.hooks_enabled = true;
+    session.clipboard_enabled = true;
+    // 前回クラッシュなどで中断したセッションがあれば再開するか尋ねる
+    session.recovered = recovery::find_orphan();
+
+    // --tui: ウィンドウを開かずに端末で表示する
+    if cli.tui {
+        // 端末の画面は一定間隔で描き直すので、リクエストを受けても起こす必要はない
+        session.listen(|| {});
+        if let Err(e) = tui::run(session) {
+            eprintln!("{}", tr!("tui_failed", error = e));
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     // アプリケーションの実行
     eframe::run_native(
         "Stop Watch",
         options,
         Box::new(move |cc| {
-            // 日本語フォントの設定
-            setup_custom_fonts(&cc.egui_ctx);
-            // Windows では Win32 API を使ってウィンドウを最前面に設定する
-            #[cfg(target_os = "windows")]
-            {
-                let title = "Stop Watch".to_string();
-                std::thread::spawn(move || {
-                    // 少し待ってウィンドウが作られるのを待つ
-                    std::thread::sleep(std::time::Duration::from_millis(200));
-                    use widestring::U16CString;
-                    use winapi::shared::windef::HWND;
-                    use winapi::um::winuser::{FindWindowW, SetWindowPos, SWP_NOMOVE, SWP_NOSIZE, HWND_TOPMOST};
-
-                    let wide = U16CString::from_str(&title).unwrap();
-                    unsafe {
-                        let hwnd: HWND = FindWindowW(std::ptr::null_mut(), wide.as_ptr());
-                        if !hwnd.is_null() {
-                            SetWindowPos(hwnd, HWND_TOPMOST, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE);
-                        }
-                    }
-                });
-            }
-            Ok(Box::new(MyApp::new(task_name.clone(), memo_default.clone(), end_minutes)))
+            // フォントの設定（日本語と、設定ファイルの [font]）
+            fonts::install(&cc.egui_ctx, &config.font);
+            apply_style(&cc.egui_ctx, &config);
+            // 制御 API のソケットで待ち受ける（使えなくてもウィンドウはそのまま使える）
+            let wake = cc.egui_ctx.clone();
+            session.listen(move || wake.request_repaint());
+            let mut widget = StopwatchWidget::new(session);
+            widget.pinned = Some(pinned);
+            widget.compact = compact;
+            widget.shortcuts = true;
+            widget.overlay = Some(overlay);
+            // 前回の大きさに戻したときは、メモが変わるまで大きさを合わせ直さない
+            let fitted_memo = saved_geometry.map(|_| widget.session.memo.clone());
+            let geometry_name = widget.session.name.clone();
+            let app = MyApp {
+                widget,
+                full_size,
+                compact_size,
+                fitted_memo,
+                fitting: 0,
+                corner,
+                place_pending: corner.is_some(),
+                geometry_name,
+                geometry: saved_geometry,
+                title: String::new(),
+                font: config.font.clone(),
+            };
+            Ok(Box::new(app))
         }),
     )
 }
 
-// フォント設定用の関数
-fn setup_custom_fonts(ctx: &egui::Context) {
-    // フォント設定を取得
-    let mut fonts = egui::FontDefinitions::default();
-    // Try to load Meiryo from Windows font directory when on Windows.
-    // Fallback to bundled NotoSansJP if Meiryo isn't available.
-    #[cfg(target_os = "windows")]
-    {
-        // common Meiryo filenames to try
-        let candidates = [
-            r"C:\\Windows\\Fonts\\meiryo.ttf",
-            r"C:\\Windows\\Fonts\\meiryob.ttf",
-            r"C:\\Windows\\Fonts\\meiryo.ttc",
-            r"C:\\Windows\\Fonts\\meiryob.ttc",
-        ];
-        let mut loaded_meiryo = false;
-        for p in &candidates {
-            if let Ok(bytes) = std::fs::read(p) {
-                fonts.font_data.insert(
-                    "meiryo".to_owned(),
-                    egui::FontData::from_owned(bytes).into(),
-                );
-                // Put Meiryo at highest priority for proportional family
-                fonts
-                    .families
-                    .entry(FontFamily::Proportional)
-                    .or_default()
-                    .insert(0, "meiryo".to_owned());
-                // also add to monospace family as a fallback for CJK characters
-                fonts
-                    .families
-                    .entry(FontFamily::Monospace)
-                    .or_default()
-                    .push("meiryo".to_owned());
-                loaded_meiryo = true;
-                break;
+// taskstopwatch ctl: 実行中のウィンドウにリクエストを送って終了する
+fn run_ctl(request: control::Request) -> ! {
+    let is_status = matches!(request, control::Request::Status);
+    match control::send(&request) {
+        Ok(response) if response.ok => {
+            // status は状態を1行の JSON で出力する（シェルのプロンプトやエディタから読む用）
+            if is_status
+                && let Some(status) = &response.status
+                && let Ok(json) = serde_json::to_string(status)
+            {
+                println!("{}", json);
             }
+            std::process::exit(0);
         }
-
-        if !loaded_meiryo {
-            // fallback to bundled Noto
-            fonts.font_data.insert(
-                "noto_sans_jp".to_owned(),
-                egui::FontData::from_static(include_bytes!("../assets/NotoSansJP-VariableFont_wght.ttf")).into(),
-            );
-            fonts
-                .families
-                .entry(FontFamily::Proportional)
-                .or_default()
-                .insert(0, "noto_sans_jp".to_owned()); // 一番優先度高く追加
-            fonts
-                .families
-                .entry(FontFamily::Monospace)
-                .or_default()
-                .push("noto_sans_jp".to_owned());
+        Ok(response) => {
+            eprintln!("{}", tr!("error", error = response.error.unwrap_or_default()));
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", tr!("error", error = e));
+            std::process::exit(1);
         }
     }
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        // on non-Windows, use bundled Noto
-        fonts.font_data.insert(
-            "noto_sans_jp".to_owned(),
-            egui::FontData::from_static(include_bytes!("../assets/NotoSansJP-VariableFont_wght.ttf")).into(),
-        );
-        fonts
-            .families
-            .entry(FontFamily::Proportional)
-            .or_default()
-            .insert(0, "noto_sans_jp".to_owned()); // 一番優先度高く追加
-        fonts
-            .families
-            .entry(FontFamily::Monospace)
-            .or_default()
-            .push("noto_sans_jp".to_owned());
-    }
-    
-    // フォント設定を適用
-    ctx.set_fonts(fonts);
-    // テキストを黒に固定する
-    //ctx.style_mut(|s| s.visuals.override_text_color = Some(Color32::BLACK));
-
+// 設定ファイルの色を egui の Visuals に反映する（設定の再読み込み時にも呼ぶ）
+fn apply_style(ctx: &egui::Context, config: &config::Config) {
     // 背景色を黄色にする（egui の Visuals を使う）
     ctx.style_mut(|s| {
         // Light モードをベースにして背景を置き換えるのが素直です
         //s.visuals = egui::Visuals::light();
-        // 背景（ウィンドウの底面）を黄色に設定
-        s.visuals.window_fill = egui::Color32::from_rgb(255, 255, 0); // やや薄い黄色
+        // 背景（ウィンドウの底面）を設定（既定は黄色）
+        s.visuals.window_fill = config.colors.window_fill.0;
         // 必要なら他の要素色も調整
-        s.visuals.panel_fill = egui::Color32::from_rgb(255, 255, 0);
-        s.visuals.override_text_color = Some(egui::Color32::BLACK); // 文字は黒に
+        s.visuals.panel_fill = config.colors.window_fill.0;
+        s.visuals.override_text_color = Some(config.colors.text.0); // 文字色（既定は黒）
     });
+}
 
+fn window_level(pinned: bool) -> egui::WindowLevel {
+    if pinned {
+        egui::WindowLevel::AlwaysOnTop
+    } else {
+        egui::WindowLevel::Normal
+    }
 }
 
-// アプリケーションの状態を保持する構造体
+// ウィンドウのアプリケーション（表示は StopwatchWidget に任せる）
 struct MyApp {
-    name: String,
-    name_edit: bool,
-    start: Instant,
-    clip_msg: Option<String>,
-    // memo state: the full text, and whether we're in edit mode
-    memo: String,
-    memo_edit: bool,
-    // optional end time (countdown) in Instant and local datetime for display
-    end_instant: Option<Instant>,
-    end_time_local: Option<chrono::DateTime<Local>>,
+    widget: StopwatchWidget,
+    // 通常の表示とミニバーの最後の大きさ
+    full_size: egui::Vec2,
+    compact_size: egui::Vec2,
+    // 表示内容に大きさを合わせたときのメモ（メモが変わったら合わせ直す）
+    fitted_memo: Option<String>,
+    // 大きさを合わせる残りのフレーム数（幅が変わると折り返しが変わって高さも変わるので、数フレーム続ける）
+    fitting: u8,
+    // --position で指定された画面の隅と、大きさが変わってまだ置き直していないか
+    corner: Option<geometry::Corner>,
+    place_pending: bool,
+    // 位置と大きさを保存する作業名と、最後の位置と大きさ
+    geometry_name: String,
+    geometry: Option<geometry::Geometry>,
+    // 最後に設定したウィンドウのタイトル
+    title: String,
+    // 最後に設定したフォント（[font] が変わったときだけ設定し直す）
+    font: config::FontConfig,
 }
 
-impl Default for MyApp {
-    fn default() -> Self {
-        Self {
-            name: "山田太郎".to_owned(),
-            name_edit: false,
-            start: Instant::now(),
-            clip_msg: None,
-            memo: "**メモを編集**".to_owned(),
-            memo_edit: false,
-            end_instant: None,
-            end_time_local: None,
+impl MyApp {
+    // 通常の表示の大きさを表示内容に合わせ、--position の隅に置き直す
+    fn fit_window(&mut self, ctx: &egui::Context, content_size: Option<egui::Vec2>) {
+        let session = &self.widget.session;
+        let window = &session.config.window;
+        // 作業が変わったら前の作業の位置と大きさを保存し、新しい作業で前回使った位置と大きさに戻す
+        if !self.widget.is_editing() && session.name != self.geometry_name {
+            self.save_geometry();
+            self.geometry_name = session.name.clone();
+            if let Some(g) = geometry::load(&session.name) {
+                self.full_size = g.size();
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(g.size()));
+                if self.corner.is_none() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(g.position()));
+                }
+                self.fitted_memo = Some(session.memo.clone());
+                self.place_pending = self.corner.is_some();
+            }
+        }
+        // メモが変わったら（編集中は確定してから）表示内容に合わせ直す
+        if window.auto_size && !self.widget.is_editing() && self.fitted_memo.as_ref() != Some(&session.memo) {
+            self.fitted_memo = Some(session.memo.clone());
+            self.fitting = 3;
+        }
+        if self.fitting > 0
+            && let Some(content) = content_size
+        {
+            self.fitting -= 1;
+            let size = window.fit(content);
+            let current = ctx.input(|i| i.viewport().inner_rect).map(|r| r.size());
+            if current.is_none_or(|c| (c - size).length() > 0.5) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+                self.full_size = size;
+                self.place_pending = self.corner.is_some();
+            }
+            ctx.request_repaint();
+        }
+        // 画面の大きさが分かったら、枠を含めた大きさで隅に置く
+        if self.place_pending
+            && let Some(corner) = self.corner
+            && let Some(monitor) = ctx.input(|i| i.viewport().monitor_size)
+        {
+            let frame = ctx
+                .input(|i| i.viewport().outer_rect.zip(i.viewport().inner_rect))
+                .map_or(egui::Vec2::ZERO, |(outer, inner)| outer.size() - inner.size());
+            let position = corner.position(monitor, self.full_size + frame);
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(position));
+            self.place_pending = false;
         }
     }
-}
 
-impl MyApp {
-    fn new(name: String, memo_default: String, end_minutes: Option<u64>) -> Self {
-        let start = Instant::now();
-        // compute optional end times
-        let (end_instant, end_time_local) = if let Some(m) = end_minutes {
-            let secs = m.saturating_mul(60);
-            let ei = start + Duration::from_secs(secs);
-            let el = Local::now() + ChronoDuration::minutes(m as i64);
-            (Some(ei), Some(el))
-        } else {
-            (None, None)
-        };
-
-        Self {
-            name,
-            name_edit: false,
-            start,
-            clip_msg: None,
-            memo: memo_default,
-            memo_edit: false,
-            end_instant,
-            end_time_local,
+    // 今の作業名で位置と大きさを保存する
+    fn save_geometry(&self) {
+        if let Some(g) = self.geometry
+            && let Err(e) = geometry::save(&self.geometry_name, g)
+        {
+            eprintln!("{}", tr!("geometry_save_failed", error = e));
         }
     }
 }
 
-fn new_frame(bgcolor: egui::Color32) -> egui::Frame {
-    // Use the current egui API: Frame::new(), CornerRadius::same, Stroke::new
-    egui::Frame::new()
-        .fill(bgcolor)
-        .stroke(egui::Stroke::new(0.0, egui::Color32::BLACK))
-        .corner_radius(egui::CornerRadius::same(0))
-}
-
-// アプリケーションの描画とロジックを実装
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // compute current time and elapsed first so we can decide background color
-        let now_instant = Instant::now();
-        let elapsed = now_instant.duration_since(self.start);
-        let secs = elapsed.as_secs();
-        let elapsed_h = secs / 3600;
-        let elapsed_m = (secs % 3600) / 60;
-        let elapsed_s = secs % 60;
-
-        // If we have an end_instant and it's passed, switch background to a light red
-        let is_over = if let Some(end_inst) = self.end_instant {
-            now_instant > end_inst
-        } else {
-            false
-        };
-        let bgcolor = if is_over {
-            // light red background when over time
-            egui::Color32::from_rgb(255, 200, 200)
-        } else {
-            // normal yellow
-            egui::Color32::from_rgb(255, 250, 0)
-        };
-
         egui::CentralPanel::default()
-            .frame(new_frame(bgcolor))
+            .frame(egui::Frame::NONE)
             .show(ctx, |ui| {
-                // Add a small left margin (~2mm) in pixels computed from points and
-                // the current pixels_per_point scaling so it respects DPI scaling.
-                let mm = 2.0_f32;
-                // convert mm -> inches -> points (1pt = 1/72 inch), then to pixels
-                let points = (mm / 25.4_f32) * 72.0_f32;
-                let left_px = points * ctx.pixels_per_point();
-
-                ui.horizontal(|ui| {
-                    ui.add_space(left_px);
-                    ui.vertical(|ui| {
-                        // use precomputed current time and elapsed
-                        let hh = elapsed_h;
-                        let mm = elapsed_m;
-                        let ss = elapsed_s;
-
-                        // 終了時刻（あれば）を上部に表示（〆の右に残り/経過時間を表示）
-                        if let Some(end_local) = &self.end_time_local {
-                            if let Some(end_inst) = self.end_instant {
-                                // compute remaining or over time text
-                                if now_instant <= end_inst {
-                                    let rem = end_inst.duration_since(now_instant);
-                                    let rsecs = rem.as_secs();
-                                    let rh = rsecs / 3600;
-                                    let rm = (rsecs % 3600) / 60;
-                                    let rs = rsecs % 60;
-                                    ui.horizontal(|ui| {
-                                        ui.label(
-                                            RichText::new(format!("〆{}", end_local.format("%H:%M")))
-                                                .color(Color32::RED)
-                                                .size(22.0)
-                                                .strong(),
-                                        );
-                                        ui.add_space(8.0);
-                                        ui.label(
-                                            RichText::new(format!("残り時間 {:02}:{:02}", rm, rs))
-                                                .color(Color32::BLACK)
-                                                .size(14.0)
-                                                .strong(),
-                                        );
-                                    });
-                                } else {
-                                    let over = now_instant.duration_since(end_inst);
-                                    let osecs = over.as_secs();
-                                    let oh = osecs / 3600;
-                                    let om = (osecs % 3600) / 60;
-                                    let os = osecs % 60;
-                                    ui.horizontal(|ui| {
-                                        ui.label(
-                                            RichText::new(format!("〆{}", end_local.format("%H:%M")))
-                                                .color(Color32::RED)
-                                                .size(22.0)
-                                                .strong(),
-                                        );
-                                        ui.add_space(8.0);
-                                        ui.label(
-                                            RichText::new(format!("{:02}:{:02} 超過", om, os))
-                                                .color(Color32::BLACK)
-                                                .size(14.0)
-                                                .strong(),
-                                        );
-                                    });
-                                }
-                            } else {
-                                // end_time_local present but no instant (shouldn't happen normally)
-                                ui.label(
-                                    RichText::new(format!("〆{}", end_local.format("%H:%M")))
-                                        .color(Color32::RED)
-                                        .size(22.0)
-                                        .strong(),
-                                );
-                            }
-                        }
-
-                        // 経過時間を hh:mm:ss 形式で表示
-                        ui.label(
-                            RichText::new(format!("{:02}:{:02} 経過", mm, ss))
-                                .color(Color32::BLACK)
-                                .size(20.0)       // フォントサイズ（ポイント）
-                                .strong()         // 太字
-                        );
-                        // 作業名表示: ラベルモード / 編集モードを切り替え
-                        if !self.name_edit {
-                            // Make task name larger and bold for prominence
-                            let label = RichText::new(&self.name)
-                                .color(Color32::BLACK)
-                                .size(22.0)
-                                .strong();
-                            if ui.add(egui::Label::new(label).sense(Sense::click())).clicked() {
-                                self.name_edit = true;
-                            }
-                        } else {
-                            // 編集モード: 1行入力。Esc で確定してラベルモードに戻る。
-                            let mut name_buf = self.name.clone();
-                            let _resp = ui.add(egui::TextEdit::singleline(&mut name_buf));
-                            // イベントで Escape を検出
-                            let events = ctx.input(|i| i.events.clone());
-                            let mut commit = false;
-                            for ev in events.iter() {
-                                if let egui::Event::Key { key, pressed, .. } = ev {
-                                    if *pressed && *key == Key::Escape {
-                                        commit = true;
-                                    }
-                                }
-                            }
-                            // 反映
-                            self.name = name_buf;
-                            if commit {
-                                self.name_edit = false;
-                            }
-                        }
-
-                        // ----- メモ領域 -----
-                        ui.separator();
-                        if !self.memo_edit {
-                            // ラベルモード: 行ごとにチェックボックス行を解釈して表示
-                            let mut lines: Vec<String> = self.memo.lines().map(|s| s.to_string()).collect();
-                            for i in 0..lines.len() {
-                                let line = &lines[i];
-                                let trimmed = line.trim_start();
-                                if trimmed.starts_with("- [ ]") || trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]") {
-                                    // checkbox line
-                                    let checked = trimmed.chars().nth(3) == Some('x') || trimmed.chars().nth(3) == Some('X');
-                                    let rhs = trimmed[5..].trim_start().to_string();
-                                    ui.horizontal(|ui| {
-                                        let mut checked_bool = checked;
-                                        let resp = ui.checkbox(&mut checked_bool, "");
-                                        if resp.clicked() {
-                                            // toggle in memo: modify lines vec and then assign back to self.memo
-                                            let mut t = lines[i].clone();
-                                            if t.contains("- [ ]") {
-                                                t = t.replacen("- [ ]", "- [x]", 1);
-                                            } else {
-                                                t = t.replacen("- [x]", "- [ ]", 1);
-                                                t = t.replacen("- [X]", "- [ ]", 1);
-                                            }
-                                            lines[i] = t;
-                                            self.memo = lines.join("\n");
-                                        }
-                                        let label = RichText::new(rhs).color(Color32::BLACK);
-                                        if ui.add(egui::Label::new(label).sense(Sense::click())).clicked() {
-                                            self.memo_edit = true;
-                                        }
-                                    });
-                                } else {
-                                    // normal line: support **bold** naive replacement
-                                    let text = if line.contains("**") {
-                                        let s = line.replace("**", "");
-                                        RichText::new(s).color(Color32::BLACK).strong()
-                                    } else {
-                                        RichText::new(line.as_str()).color(Color32::BLACK)
-                                    };
-                                    if ui.add(egui::Label::new(text).sense(Sense::click())).clicked() {
-                                        self.memo_edit = true;
-                                    }
-                                }
-                            }
+                let output = self.widget.show(ui);
+                if output.config_reloaded {
+                    // フォントを探して読み込むのは [font] が変わったときだけ
+                    if self.widget.session.config.font != self.font {
+                        self.font = self.widget.session.config.font.clone();
+                        fonts::install(ctx, &self.font);
+                    }
+                    apply_style(ctx, &self.widget.session.config);
+                }
+                // ポモドーロのフェーズが切り替わったらウィンドウで知らせる
+                if output.phase_changed {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                        egui::UserAttentionType::Informational,
+                    ));
+                }
+                // 最前面表示の切り替え
+                if output.pin_toggled
+                    && let Some(pinned) = self.widget.pinned
+                {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(window_level(pinned)));
+                }
+                // ミニバーの切り替え: 切り替える前の表示の大きさを覚えて、切り替えた先の大きさに戻す
+                if output.compact_toggled {
+                    let compact = self.widget.compact;
+                    if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+                        if compact {
+                            self.full_size = rect.size();
                         } else {
-                            // 編集モード: 複数行テキスト編集
-                            let mut edit = self.memo.clone();
-                            let _resp = ui.add(egui::TextEdit::multiline(&mut edit).desired_rows(6));
-                            // 入力イベントを見て Escape を確定キー、Alt+Enter を改行にする
-                            let mut commit = false;
-                            let events = ctx.input(|i| i.events.clone());
-                            for ev in events.iter() {
-                                if let egui::Event::Key { key, pressed, modifiers, .. } = ev {
-                                    if *pressed {
-                                        if *key == Key::Escape {
-                                            // Escape: 確定（編集終了）
-                                            commit = true;
-                                        } else if *key == Key::Enter {
-                                            // Alt+Enter: 改行を挿入
-                                            if modifiers.alt {
-                                                edit.push('\n');
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            // 編集内容を常に反映
-                            self.memo = edit;
-                            if commit {
-                                self.memo_edit = false;
-                            }
-                        }
-                        // 完了ボタン: 押されたら現在時刻を HHMM 形式でクリップボードに保存
-                        if ui.add(egui::Button::new("完了!")).clicked() {
-                            let now_hhmm = Local::now().format("%H%M").to_string();
-                            match Clipboard::new() {
-                                Ok(mut cb) => {
-                                    match cb.set_text(now_hhmm.clone()) {
-                                        Ok(()) => {
-                                            self.clip_msg = Some(format!("{} をクリップボードにコピーしました", now_hhmm));
-                                            // コピー成功したのでアプリを終了する
-                                            std::process::exit(0);
-                                        }
-                                        Err(e) => self.clip_msg = Some(format!("クリップボード保存に失敗: {:?}", e)),
-                                    }
-                                }
-                                Err(e) => {
-                                    self.clip_msg = Some(format!("クリップボード初期化失敗: {:?}", e));
-                                }
-                            }
+                            self.compact_size = rect.size();
                         }
+                    }
+                    let size = if compact { self.compact_size } else { self.full_size };
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(!compact));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+                }
+                // オーバーレイ表示ではクリックを下のウィンドウに通す
+                if output.overlay_toggled
+                    && let Some(overlay) = self.widget.overlay
+                {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(overlay));
+                }
+                // タイトルに残り時間などを出す（タスクバーやウィンドウの切り替えでも進み具合が分かるように）
+                let title = self.widget.session.title(Instant::now());
+                if title != self.title {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+                    self.title = title;
+                }
+                // 通常の表示では大きさを表示内容に合わせる
+                if !self.widget.compact {
+                    self.fit_window(ctx, output.content_size);
+                }
+                // 最後の位置と大きさを覚えておく（ミニバーのときは通常の表示の大きさ）
+                if let Some((outer, inner)) = ctx.input(|i| i.viewport().outer_rect.zip(i.viewport().inner_rect)) {
+                    let size = if self.widget.compact { self.full_size } else { inner.size() };
+                    self.geometry = Some(geometry::Geometry::new(outer.min, size));
+                }
+                // 枠のないミニバーはドラッグで移動する
+                if output.drag_started {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+                // 完了してキューも空ならアプリを終了する
+                // （ウィンドウを閉じて終了し、スナップショットのロックやソケットのファイルを片付ける）
+                if output.finished {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+    }
 
-                        if let Some(msg) = &self.clip_msg {
-                            ui.colored_label(Color32::BLACK, msg);
-                        }
+    // 背景はウィジェットが不透明度を反映して塗るので、ウィンドウの地は透明にする
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        [0.0; 4]
+    }
 
-                        // リアルタイム更新を促す
-                        ctx.request_repaint_after(Duration::from_millis(200));
-                    });
-                });
-            });
+    // ウィンドウを閉じたときも（完了していなければ）セッションを記録する
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_geometry();
+        let session = &mut self.widget.session;
+        let record = session.session_record(false);
+        if let Err(e) = session.record_session(&record) {
+            eprintln!("{}", tr!("history_save_failed", error = e));
+        }
     }
 }
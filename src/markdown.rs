@@ -0,0 +1,292 @@
+// メモ用の Markdown（サブセット）の解析
+// 1行ずつ解釈し、チェックボックスの切り替えで元の行を書き換えられるよう行番号を保持する。
+//
+// 対応している記法:
+//   見出し（# 〜 ######）、箇条書き（- * +）、番号付きリスト（1. / 1)）、
+//   チェックリスト（- [ ] / - [x]、インデントによる入れ子可）、引用（>）、
+//   インライン: **太字** __太字__ *斜体* _斜体_ ~~取り消し線~~ `コード` [リンク](URL)
+
+/// インラインの装飾付きテキスト片
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub strike: bool,
+    pub code: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineKind {
+    Blank,
+    Heading(usize),
+    Bullet,
+    Numbered(String),
+    Task { checked: bool },
+    Quote,
+    Paragraph,
+}
+
+/// メモ1行分の解析結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdLine {
+    /// メモ中の行番号（0始まり）
+    pub line: usize,
+    /// 行頭の空白の幅（タブは4文字分）
+    pub indent: usize,
+    pub kind: LineKind,
+    pub spans: Vec<Span>,
+}
+
+/// メモ全体を行ごとに解析する
+pub fn parse(memo: &str) -> Vec<MdLine> {
+    memo.lines().enumerate().map(|(i, l)| parse_line(i, l)).collect()
+}
+
+fn parse_line(line_no: usize, line: &str) -> MdLine {
+    let indent: usize = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let body = line.trim_start();
+    let make = |kind, text: &str| MdLine {
+        line: line_no,
+        indent,
+        kind,
+        spans: parse_inline(text),
+    };
+    if body.is_empty() {
+        return make(LineKind::Blank, "");
+    }
+    if let Some((checked, text)) = task_item(line) {
+        return make(LineKind::Task { checked }, text);
+    }
+    let hashes = body.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&hashes) && body[hashes..].starts_with(' ') {
+        return make(LineKind::Heading(hashes), body[hashes..].trim());
+    }
+    if let Some(rest) = body.strip_prefix('>') {
+        return make(LineKind::Quote, rest.trim_start());
+    }
+    if let Some(rest) = bullet(body) {
+        return make(LineKind::Bullet, rest);
+    }
+    let digits = body.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &body[digits..];
+        if let Some(text) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return make(LineKind::Numbered(body[..digits].to_owned()), text);
+        }
+    }
+    make(LineKind::Paragraph, body)
+}
+
+// "- " / "* " / "+ " で始まる箇条書きなら本文を返す
+fn bullet(body: &str) -> Option<&str> {
+    ["- ", "* ", "+ "].iter().find_map(|m| body.strip_prefix(m))
+}
+
+/// チェックリストの行なら (チェック済みか, 本文) を返す
+pub fn task_item(line: &str) -> Option<(bool, &str)> {
+    let rest = bullet(line.trim_start())?;
+    let checked = if rest.starts_with("[ ]") {
+        false
+    } else if rest.starts_with("[x]") || rest.starts_with("[X]") {
+        true
+    } else {
+        return None;
+    };
+    Some((checked, rest[3..].trim()))
+}
+
+/// memo の line 行目のチェックボックスを切り替えた新しいメモを返す
+pub fn toggle_task(memo: &str, line: usize) -> String {
+    let lines: Vec<String> = memo
+        .lines()
+        .enumerate()
+        .map(|(i, l)| {
+            if i != line {
+                return l.to_owned();
+            }
+            let Some((checked, _)) = task_item(l) else {
+                return l.to_owned();
+            };
+            // 行頭の空白と箇条書き記号の後ろにある [ ] / [x] だけを書き換える
+            let pos = l.find('[').expect("チェックリストの行には [ がある");
+            let mark = if checked { "[ ]" } else { "[x]" };
+            format!("{}{}{}", &l[..pos], mark, &l[pos + 3..])
+        })
+        .collect();
+    // 末尾の改行は元のメモに合わせて残す
+    let mut toggled = lines.join("\n");
+    if memo.ends_with('\n') {
+        toggled.push('\n');
+    }
+    toggled
+}
+
+/// インラインの装飾を解析する
+pub fn parse_inline(text: &str) -> Vec<Span> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Span::default();
+    let mut cur = String::new();
+    let mut i = 0;
+
+    // 現在の装飾でたまった文字列を1つの Span にする
+    fn flush(spans: &mut Vec<Span>, cur: &mut String, style: &Span) {
+        if !cur.is_empty() {
+            spans.push(Span {
+                text: std::mem::take(cur),
+                ..style.clone()
+            });
+        }
+    }
+    let rest_has = |from: usize, marker: &str| -> bool {
+        chars[from..].iter().collect::<String>().contains(marker)
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        // `コード`（中は装飾しない）
+        if c == '`'
+            && let Some(len) = chars[i + 1..].iter().position(|&x| x == '`')
+        {
+            flush(&mut spans, &mut cur, &style);
+            spans.push(Span {
+                text: chars[i + 1..i + 1 + len].iter().collect(),
+                code: true,
+                ..Span::default()
+            });
+            i += len + 2;
+            continue;
+        }
+        // [テキスト](URL)
+        if c == '['
+            && let Some(close) = chars[i + 1..].iter().position(|&x| x == ']')
+        {
+            let close = i + 1 + close;
+            if chars.get(close + 1) == Some(&'(')
+                && let Some(end) = chars[close + 2..].iter().position(|&x| x == ')')
+            {
+                let end = close + 2 + end;
+                flush(&mut spans, &mut cur, &style);
+                spans.push(Span {
+                    text: chars[i + 1..close].iter().collect(),
+                    link: Some(chars[close + 2..end].iter().collect()),
+                    ..style.clone()
+                });
+                i = end + 1;
+                continue;
+            }
+        }
+        // **太字** / __太字__ / ~~取り消し線~~（閉じる記号がある場合だけ装飾として扱う）
+        if (c == '*' || c == '_' || c == '~') && next == Some(c) {
+            let marker: String = [c, c].iter().collect();
+            let is_on = if c == '~' { style.strike } else { style.bold };
+            // 開く記号の直後が空白なら装飾にしない（"a ** b" など）
+            let opens = chars.get(i + 2).is_some_and(|n| !n.is_whitespace()) && rest_has(i + 2, &marker);
+            if is_on || opens {
+                flush(&mut spans, &mut cur, &style);
+                if c == '~' {
+                    style.strike = !is_on;
+                } else {
+                    style.bold = !is_on;
+                }
+                i += 2;
+                continue;
+            }
+        }
+        // *斜体* / _斜体_（snake_case のような単語中の _ は装飾にしない）
+        if c == '*' || c == '_' {
+            let prev_alnum = i > 0 && chars[i - 1].is_alphanumeric();
+            let next_alnum = next.is_some_and(|n| n.is_alphanumeric());
+            let in_word = c == '_' && prev_alnum && next_alnum;
+            let opens = next.is_some_and(|n| !n.is_whitespace() && n != c) && rest_has(i + 1, &c.to_string());
+            if !in_word && (style.italic || opens) {
+                flush(&mut spans, &mut cur, &style);
+                style.italic = !style.italic;
+                i += 1;
+                continue;
+            }
+        }
+        cur.push(c);
+        i += 1;
+    }
+    flush(&mut spans, &mut cur, &style);
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Span {
+        Span {
+            text: text.to_owned(),
+            ..Span::default()
+        }
+    }
+
+    #[test]
+    fn inline_styles() {
+        let spans = parse_inline("a **b** *c* ~~d~~ `e*f*`");
+        assert_eq!(
+            spans,
+            vec![
+                plain("a "),
+                Span { bold: true, ..plain("b") },
+                plain(" "),
+                Span { italic: true, ..plain("c") },
+                plain(" "),
+                Span { strike: true, ..plain("d") },
+                plain(" "),
+                Span { code: true, ..plain("e*f*") },
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_link_keeps_surrounding_style() {
+        let spans = parse_inline("**[doc](https://example.com)**");
+        assert_eq!(
+            spans,
+            vec![Span {
+                bold: true,
+                link: Some("https://example.com".to_owned()),
+                ..plain("doc")
+            }]
+        );
+    }
+
+    #[test]
+    fn unmatched_markers_are_plain_text() {
+        assert_eq!(parse_inline("snake_case_name"), vec![plain("snake_case_name")]);
+        assert_eq!(parse_inline("a ** b"), vec![plain("a ** b")]);
+        assert_eq!(parse_inline("2 * 3"), vec![plain("2 * 3")]);
+        assert_eq!(parse_inline("[not a link]"), vec![plain("[not a link]")]);
+    }
+
+    #[test]
+    fn toggle_task_rewrites_only_the_given_line() {
+        let memo = "- [ ] a\n  * [x] b\n- [ ] c";
+        assert_eq!(toggle_task(memo, 0), "- [x] a\n  * [x] b\n- [ ] c");
+        assert_eq!(toggle_task(memo, 1), "- [ ] a\n  * [ ] b\n- [ ] c");
+    }
+
+    #[test]
+    fn toggle_task_ignores_other_lines() {
+        let memo = "# [ ] heading\n- plain [ ]\n";
+        assert_eq!(toggle_task(memo, 0), memo);
+        assert_eq!(toggle_task(memo, 1), memo);
+        assert_eq!(toggle_task(memo, 5), memo);
+    }
+
+    #[test]
+    fn toggle_task_keeps_trailing_newline() {
+        assert_eq!(toggle_task("- [X] a\n", 0), "- [ ] a\n");
+    }
+}
@@ -0,0 +1,139 @@
+// Markdown ファイルと同期するメモ
+// --memo-file で指定したファイルをメモとして読み込み、チェックボックスの切り替えや
+// 編集内容をファイルに書き戻す。エディタなどでファイルが外部から変更された場合は読み込み直す。
+use crate::tr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub struct MemoFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    // 最後に読み書きしたメモの内容（改行コード・末尾の改行を除いた形）
+    last: String,
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+impl MemoFile {
+    /// ファイルを開いて、メモとして表示する内容を返す
+    pub fn open(path: &Path) -> Result<(Self, String), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| tr!("memo_file_read_failed", path = path.display(), error = e))?;
+        let memo = normalize(&text);
+        let file = Self {
+            path: path.to_path_buf(),
+            modified: modified_time(path),
+            last: memo.clone(),
+            crlf: text.contains("\r\n"),
+            trailing_newline: text.ends_with('\n'),
+        };
+        Ok((file, memo))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// ファイルが外部で変更されていれば新しい内容を返す
+    pub fn poll(&mut self) -> Option<String> {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        let text = std::fs::read_to_string(&self.path).ok()?;
+        let memo = normalize(&text);
+        if memo == self.last {
+            return None;
+        }
+        self.crlf = text.contains("\r\n");
+        self.trailing_newline = text.ends_with('\n');
+        self.last = memo.clone();
+        Some(memo)
+    }
+
+    /// メモが変わっていればファイルに書き戻す（元の改行コードと末尾の改行は保つ）
+    pub fn write(&mut self, memo: &str) -> std::io::Result<()> {
+        if memo == self.last {
+            return Ok(());
+        }
+        let newline = if self.crlf { "\r\n" } else { "\n" };
+        let mut text = memo.lines().collect::<Vec<_>>().join(newline);
+        if self.trailing_newline {
+            text.push_str(newline);
+        }
+        std::fs::write(&self.path, text)?;
+        self.last = memo.to_owned();
+        // 自分で書いた変更を外部の変更として読み込み直さないようにする
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+}
+
+// 改行コードを \n にそろえ、末尾の改行を取り除く
+fn normalize(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join("\n")
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn temp_file(name: &str, text: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("taskstopwatch-memo-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    // 更新時刻の精度に左右されないよう、変更時刻を明示的に設定する
+    fn touch(path: &Path, secs: u64) {
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+    }
+
+    #[test]
+    fn writes_only_when_the_memo_changes() {
+        let path = temp_file("write.md", "- [ ] a\r\n- [ ] b\r\n");
+        touch(&path, 1_000);
+        let (mut file, memo) = MemoFile::open(&path).unwrap();
+        assert_eq!(memo, "- [ ] a\n- [ ] b");
+
+        file.write(&memo).unwrap();
+        assert_eq!(modified_time(&path), Some(UNIX_EPOCH + Duration::from_secs(1_000)));
+
+        file.write("- [x] a\n- [ ] b").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- [x] a\r\n- [ ] b\r\n");
+        // 自分で書いた内容は外部の変更として扱わない
+        assert_eq!(file.poll(), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn poll_picks_up_external_edits() {
+        let path = temp_file("poll.md", "- [ ] a\n");
+        touch(&path, 1_000);
+        let (mut file, _) = MemoFile::open(&path).unwrap();
+        assert_eq!(file.poll(), None);
+
+        std::fs::write(&path, "- [ ] a\n- [ ] c\n").unwrap();
+        touch(&path, 2_000);
+        assert_eq!(file.poll().as_deref(), Some("- [ ] a\n- [ ] c"));
+        assert_eq!(file.poll(), None);
+
+        // 時刻だけ変わって内容が同じなら何も返さない
+        touch(&path, 3_000);
+        assert_eq!(file.poll(), None);
+
+        // 読み込み直した内容と同じなら書き戻さない
+        file.write("- [ ] a\n- [ ] c").unwrap();
+        assert_eq!(modified_time(&path), Some(UNIX_EPOCH + Duration::from_secs(3_000)));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,101 @@
+// 一時停止の管理
+// 一時停止していた区間を記録し、経過時間から差し引けるようにする。
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 一時停止していた1区間（履歴に記録する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseInterval {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub secs: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct PauseTracker {
+    // 一時停止中なら、その開始時刻（Instant と壁時計）と、
+    // 中断したセッションを再開した場合は再開前から続いていた一時停止の長さ
+    current: Option<(Instant, DateTime<Local>, Duration)>,
+    // 終了済みの一時停止区間の合計
+    total: Duration,
+    intervals: Vec<PauseInterval>,
+}
+
+impl PauseTracker {
+    pub fn is_paused(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if self.current.is_none() {
+            self.current = Some((now, Local::now(), Duration::ZERO));
+        }
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if let Some((since, since_local, before)) = self.current.take() {
+            let d = now.saturating_duration_since(since) + before;
+            self.total += d;
+            self.intervals.push(PauseInterval {
+                start: since_local,
+                end: since_local + chrono::Duration::from_std(d).unwrap_or_default(),
+                secs: d.as_secs(),
+            });
+        }
+    }
+
+    pub fn toggle(&mut self, now: Instant) {
+        if self.is_paused() {
+            self.resume(now);
+        } else {
+            self.pause(now);
+        }
+    }
+
+    /// now までに一時停止していた時間の合計（進行中の一時停止を含む）
+    pub fn paused_total(&self, now: Instant) -> Duration {
+        match self.current {
+            Some((since, _, before)) => self.total + now.saturating_duration_since(since) + before,
+            None => self.total,
+        }
+    }
+
+    /// 記録用の一時停止区間一覧（進行中の一時停止は now で区切る）
+    pub fn intervals(&self, now: Instant) -> Vec<PauseInterval> {
+        let mut v = self.intervals.clone();
+        if let Some((since, since_local, before)) = self.current {
+            let d = now.saturating_duration_since(since) + before;
+            v.push(PauseInterval {
+                start: since_local,
+                end: since_local + chrono::Duration::from_std(d).unwrap_or_default(),
+                secs: d.as_secs(),
+            });
+        }
+        v
+    }
+
+    /// 終了済みの一時停止区間
+    pub fn finished_intervals(&self) -> &[PauseInterval] {
+        &self.intervals
+    }
+
+    /// 一時停止中ならその開始時刻（壁時計）
+    pub fn paused_since(&self) -> Option<DateTime<Local>> {
+        self.current.map(|(_, since_local, _)| since_local)
+    }
+
+    /// 保存しておいた一時停止区間から復元する（中断したセッションの再開用）
+    pub fn restore(intervals: Vec<PauseInterval>, paused_since: Option<DateTime<Local>>, now: Instant) -> Self {
+        let total = Duration::from_secs(intervals.iter().map(|p| p.secs).sum());
+        let current = paused_since.map(|since| {
+            let before = (Local::now() - since).to_std().unwrap_or_default();
+            (now, since, before)
+        });
+        Self {
+            current,
+            total,
+            intervals,
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// ポモドーロモード
+// 作業 → 短い休憩 → 作業 → … → 作業 → 長い休憩 を繰り返す。
+// どのフェーズにいるかはポモドーロ開始からの経過時間（一時停止分を除く）だけで決まるので、
+// 状態を持たずに計算できる（中断からの再開やキューの次の作業にもそのまま引き継げる）。
+use crate::config::PomodoroConfig;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Work => tr!("phase_work"),
+            Phase::ShortBreak => tr!("phase_short_break"),
+            Phase::LongBreak => tr!("phase_long_break"),
+        }
+    }
+}
+
+/// ある時点のフェーズ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseState {
+    pub phase: Phase,
+    /// 何回目の作業か（1 〜 cycles）
+    pub cycle: u32,
+    /// フェーズの開始・終了（ポモドーロ開始からの経過時間）
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// 履歴に記録する1フェーズ分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseRecord {
+    pub phase: Phase,
+    pub cycle: u32,
+    /// 作業（タスク）の開始からの経過時間[秒]
+    pub start_secs: u64,
+    pub secs: u64,
+}
+
+fn minutes(m: u64) -> Duration {
+    Duration::from_secs(m * 60)
+}
+
+/// 経過時間 t の時点のフェーズを求める
+pub fn phase_at(config: &PomodoroConfig, t: Duration) -> PhaseState {
+    let work = minutes(config.work_minutes);
+    let short = minutes(config.short_break_minutes);
+    let long = minutes(config.long_break_minutes);
+    let cycles = config.cycles.max(1);
+    let round = work * cycles + short * (cycles - 1) + long;
+    let rounds = (t.as_secs() / round.as_secs().max(1)) as u32;
+    let mut start = round * rounds;
+    for cycle in 1..=cycles {
+        if t < start + work {
+            return PhaseState {
+                phase: Phase::Work,
+                cycle,
+                start,
+                end: start + work,
+            };
+        }
+        start += work;
+        let (phase, len) = if cycle < cycles {
+            (Phase::ShortBreak, short)
+        } else {
+            (Phase::LongBreak, long)
+        };
+        if t < start + len {
+            return PhaseState {
+                phase,
+                cycle,
+                start,
+                end: start + len,
+            };
+        }
+        start += len;
+    }
+    // 丸め誤差などで最後まで来た場合は次の作業の始まり
+    PhaseState {
+        phase: Phase::Work,
+        cycle: 1,
+        start,
+        end: start + work,
+    }
+}
+
+/// from 〜 to の間のフェーズ一覧（記録の時刻は from を 0 とする）
+pub fn phases_between(config: &PomodoroConfig, from: Duration, to: Duration) -> Vec<PhaseRecord> {
+    let mut records = Vec::new();
+    let mut t = from;
+    while t < to {
+        let state = phase_at(config, t);
+        let end = state.end.min(to);
+        if end <= t {
+            break;
+        }
+        records.push(PhaseRecord {
+            phase: state.phase,
+            cycle: state.cycle,
+            start_secs: (t - from).as_secs(),
+            secs: (end - t).as_secs(),
+        });
+        t = end;
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(cycles: u32) -> PomodoroConfig {
+        PomodoroConfig {
+            enabled: true,
+            cycles,
+            ..PomodoroConfig::default()
+        }
+    }
+
+    fn phase(config: &PomodoroConfig, m: u64) -> (Phase, u32, u64, u64) {
+        let state = phase_at(config, minutes(m));
+        (state.phase, state.cycle, state.start.as_secs() / 60, state.end.as_secs() / 60)
+    }
+
+    #[test]
+    fn phase_at_follows_the_cycle() {
+        let config = config(4);
+        assert_eq!(phase(&config, 0), (Phase::Work, 1, 0, 25));
+        assert_eq!(phase(&config, 25), (Phase::ShortBreak, 1, 25, 30));
+        assert_eq!(phase(&config, 30), (Phase::Work, 2, 30, 55));
+        assert_eq!(phase(&config, 114), (Phase::Work, 4, 90, 115));
+        assert_eq!(phase(&config, 115), (Phase::LongBreak, 4, 115, 130));
+        // 長い休憩の後は最初に戻る
+        assert_eq!(phase(&config, 130), (Phase::Work, 1, 130, 155));
+        assert_eq!(phase(&config, 287), (Phase::ShortBreak, 1, 285, 290));
+    }
+
+    #[test]
+    fn single_cycle_has_only_long_breaks() {
+        let config = config(1);
+        assert_eq!(phase(&config, 25), (Phase::LongBreak, 1, 25, 40));
+        assert_eq!(phase(&config, 40), (Phase::Work, 1, 40, 65));
+    }
+
+    #[test]
+    fn phases_between_splits_at_phase_changes() {
+        let records = phases_between(&config(4), minutes(20), minutes(35));
+        let summary: Vec<_> = records.iter().map(|r| (r.phase, r.cycle, r.start_secs, r.secs)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Phase::Work, 1, 0, 300),
+                (Phase::ShortBreak, 1, 300, 300),
+                (Phase::Work, 2, 600, 300),
+            ]
+        );
+        assert!(phases_between(&config(4), minutes(20), minutes(20)).is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+// タスクキュー
+// 複数の作業を順番に実行するためのキューファイルを読み込む。
+//
+// Markdown 形式（`## ` 見出しごとに1作業、見出し末尾の `[分]` が見積時間、本文がメモ）:
+//
+//   ## 設計レビュー [30]
+//   - [ ] 資料を読む
+//   - [ ] コメントを書く
+//
+// TOML 形式:
+//
+//   [[task]]
+//   name = "設計レビュー"
+//   memo = "- [ ] 資料を読む"
+//   estimate = 30
+use crate::tr;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// キュー中の1作業
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedTask {
+    pub name: String,
+    #[serde(default)]
+    pub memo: String,
+    /// 所要見積時間[分]
+    #[serde(default)]
+    pub estimate: Option<u64>,
+    /// メモと同期する Markdown ファイル（--memo-file）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo_file: Option<PathBuf>,
+    /// 〆の時刻（--until）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Local>>,
+}
+
+impl QueuedTask {
+    /// 見積時間が長すぎないか（制御 API で渡された作業も同じように確かめる）
+    pub fn validate(&self) -> Result<(), String> {
+        if self.estimate.is_some_and(|m| m > crate::session::MAX_ESTIMATE_MINUTES) {
+            return Err(tr!(
+                "task_estimate_too_long",
+                name = self.name,
+                max = crate::session::MAX_ESTIMATE_MINUTES,
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct QueueFile {
+    #[serde(default, rename = "task")]
+    tasks: Vec<QueuedTask>,
+}
+
+/// キューファイルを読み込む。拡張子が .toml なら TOML、それ以外は Markdown として解釈する。
+pub fn load(path: &Path) -> Result<Vec<QueuedTask>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| tr!("queue_read_failed", path = path.display(), error = e))?;
+    let is_toml = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    let mut tasks = if is_toml {
+        let file: QueueFile = toml::from_str(&text)
+            .map_err(|e| tr!("queue_parse_failed", path = path.display(), error = e))?;
+        file.tasks
+    } else {
+        parse_markdown(&text)
+    };
+    // 引数と同様に <br> を改行として扱う
+    for t in tasks.iter_mut() {
+        t.memo = t.memo.replace("<br/>", "\n").replace("<br>", "\n");
+    }
+    for t in &tasks {
+        t.validate()
+            .map_err(|e| tr!("queue_invalid", path = path.display(), error = e))?;
+    }
+    if tasks.is_empty() {
+        return Err(tr!("queue_empty", path = path.display()));
+    }
+    Ok(tasks)
+}
+
+fn parse_markdown(text: &str) -> Vec<QueuedTask> {
+    let mut tasks: Vec<QueuedTask> = Vec::new();
+    let mut memo_lines: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if let Some(last) = tasks.last_mut() {
+                last.memo = join_memo(&memo_lines);
+            }
+            memo_lines.clear();
+            let (name, estimate) = split_estimate(heading.trim());
+            tasks.push(QueuedTask {
+                name,
+                memo: String::new(),
+                estimate,
+                memo_file: None,
+                until: None,
+            });
+        } else if !tasks.is_empty() {
+            memo_lines.push(line);
+        }
+    }
+    if let Some(last) = tasks.last_mut() {
+        last.memo = join_memo(&memo_lines);
+    }
+    tasks
+}
+
+// 前後の空行を取り除いてメモにする
+fn join_memo(lines: &[&str]) -> String {
+    lines.join("\n").trim_matches('\n').to_owned()
+}
+
+// "作業名 [30]" -> ("作業名", Some(30))
+fn split_estimate(heading: &str) -> (String, Option<u64>) {
+    if let Some(rest) = heading.strip_suffix(']')
+        && let Some(pos) = rest.rfind('[')
+        && let Ok(m) = rest[pos + 1..].trim().parse::<u64>()
+    {
+        return (rest[..pos].trim().to_owned(), Some(m));
+    }
+    (heading.to_owned(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_estimate_reads_trailing_minutes() {
+        assert_eq!(split_estimate("設計レビュー [30]"), ("設計レビュー".to_owned(), Some(30)));
+        assert_eq!(split_estimate("a [b] [ 5 ]"), ("a [b]".to_owned(), Some(5)));
+        assert_eq!(split_estimate("a [b]"), ("a [b]".to_owned(), None));
+        assert_eq!(split_estimate("a [30] b"), ("a [30] b".to_owned(), None));
+        assert_eq!(split_estimate("a [-1]"), ("a [-1]".to_owned(), None));
+    }
+
+    #[test]
+    fn parse_markdown_splits_tasks_at_headings() {
+        let text = "前置きは無視する\n\n## 設計レビュー [30]\n\n- [ ] 資料を読む\n- [ ] コメントを書く\n\n## 片付け\n### 小見出しはメモ\n";
+        let tasks = parse_markdown(text);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "設計レビュー");
+        assert_eq!(tasks[0].estimate, Some(30));
+        assert_eq!(tasks[0].memo, "- [ ] 資料を読む\n- [ ] コメントを書く");
+        assert_eq!(tasks[1].name, "片付け");
+        assert_eq!(tasks[1].estimate, None);
+        assert_eq!(tasks[1].memo, "### 小見出しはメモ");
+    }
+
+    #[test]
+    fn parse_markdown_without_headings_is_empty() {
+        assert!(parse_markdown("- [ ] a\n# 見出し1\n").is_empty());
+    }
+
+    #[test]
+    fn validate_rejects_long_estimates() {
+        let mut task = QueuedTask {
+            name: "a".to_owned(),
+            memo: String::new(),
+            estimate: Some(crate::session::MAX_ESTIMATE_MINUTES),
+            memo_file: None,
+            until: None,
+        };
+        assert!(task.validate().is_ok());
+        task.estimate = Some(crate::session::MAX_ESTIMATE_MINUTES + 1);
+        assert!(task.validate().is_err());
+    }
+}
@@ -0,0 +1,286 @@
+// 実行中セッションのスナップショット
+// ウィンドウを閉じる前にプロセスが終了した（クラッシュ・再起動など）場合に備えて、
+// 実行中のセッションを定期的にデータディレクトリへ保存しておき、次回起動時に再開できるようにする。
+// 正常に終了した（履歴に記録した）セッションのスナップショットは削除する。
+// スナップショットはプロセスごとのファイルに保存し、終了したプロセスのものだけを復元の対象にする。
+use crate::history::{self, SessionRecord};
+use crate::config::PomodoroConfig;
+use crate::pause::PauseInterval;
+use crate::queue::QueuedTask;
+use crate::splits::SplitTimes;
+use crate::tr;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub memo: String,
+    /// メモと同期している Markdown ファイル
+    #[serde(default)]
+    pub memo_file: Option<PathBuf>,
+    /// 開始時刻（Instant は再起動をまたげないので壁時計で保存する）
+    pub start: DateTime<Local>,
+    pub estimate_minutes: Option<u64>,
+    /// --until で指定された〆の時刻
+    #[serde(default)]
+    pub until: Option<DateTime<Local>>,
+    /// 終了済みの一時停止区間と、保存時に一時停止中だった場合はその開始時刻
+    pub pauses: Vec<PauseInterval>,
+    pub paused_since: Option<DateTime<Local>>,
+    /// チェックリスト項目の番号 -> チェック時の経過時間[秒]
+    pub splits: BTreeMap<usize, u64>,
+    /// splits の番号が指しているチェックリスト項目の文言（保存時にメモを編集中だった場合はメモと食い違う）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub split_items: Vec<String>,
+    pub queue: Vec<QueuedTask>,
+    /// ポモドーロモードの場合、この作業を始めた時点のポモドーロの経過時間[秒]
+    #[serde(default)]
+    pub pomodoro: Option<u64>,
+    /// スナップショットを保存した時刻
+    pub saved_at: DateTime<Local>,
+}
+
+impl Snapshot {
+    /// splits の番号が指している項目の文言（古いスナップショットにはないので、そのときはメモから求める）
+    pub fn split_items(&self) -> Vec<String> {
+        if self.split_items.is_empty() {
+            history::checklist_from_memo(&self.memo).into_iter().map(|c| c.text).collect()
+        } else {
+            self.split_items.clone()
+        }
+    }
+
+    /// at 時点での正味の作業時間[秒]（開始からの壁時計の時間 - 一時停止していた時間）
+    pub fn elapsed_secs(&self, at: DateTime<Local>) -> u64 {
+        let gross = (at - self.start).num_seconds().max(0) as u64;
+        let mut paused: u64 = self.pauses.iter().map(|p| p.secs).sum();
+        if let Some(since) = self.paused_since {
+            paused += (at - since).num_seconds().max(0) as u64;
+        }
+        gross.saturating_sub(paused)
+    }
+
+    /// 中断したセッションを、最後に保存した時点で終わったものとして履歴用の記録にする
+    pub fn to_record(&self, pomodoro: &PomodoroConfig) -> SessionRecord {
+        let mut checklist = history::checklist_from_memo(&self.memo);
+        let mut splits = SplitTimes::from_ticks(
+            self.splits.iter().map(|(&i, &t)| (i, Duration::from_secs(t))).collect(),
+            self.split_items(),
+        );
+        splits.sync(&checklist);
+        for (i, item) in checklist.iter_mut().enumerate() {
+            item.split_secs = splits.split(i).map(|d| d.as_secs());
+        }
+        let mut pauses = self.pauses.clone();
+        if let Some(since) = self.paused_since {
+            let secs = (self.saved_at - since).num_seconds().max(0) as u64;
+            pauses.push(PauseInterval {
+                start: since,
+                end: self.saved_at,
+                secs,
+            });
+        }
+        let elapsed_secs = self.elapsed_secs(self.saved_at);
+        let phases = match self.pomodoro {
+            Some(offset) => crate::pomodoro::phases_between(
+                pomodoro,
+                Duration::from_secs(offset),
+                Duration::from_secs(offset + elapsed_secs),
+            ),
+            None => Vec::new(),
+        };
+        SessionRecord {
+            name: self.name.clone(),
+            start: self.start,
+            end: self.saved_at,
+            elapsed_secs,
+            estimate_minutes: self.estimate_minutes,
+            deadline: self
+                .until
+                .or_else(|| self.estimate_minutes.map(|m| crate::session::add_minutes(self.start, m))),
+            memo: self.memo.clone(),
+            checklist,
+            paused_secs: pauses.iter().map(|p| p.secs).sum(),
+            pauses,
+            phases,
+            completed: false,
+        }
+    }
+}
+
+/// スナップショットを置くディレクトリ（例: ~/.local/share/taskstopwatch/running/）
+pub fn snapshot_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("taskstopwatch").join("running"))
+}
+
+fn not_found() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, tr!("data_dir_missing"))
+}
+
+/// このプロセスのスナップショットのファイル（<ID>.json）
+/// 同時に起動したウィンドウどうしで上書きしないよう、プロセスごとに別のファイルに保存する。
+/// 実行中は <ID>.lock をロックしておき、ロックできるスナップショットを中断したセッションとみなす。
+#[derive(Debug)]
+pub struct SnapshotFile {
+    path: PathBuf,
+    lock_path: PathBuf,
+    _lock: File,
+}
+
+impl SnapshotFile {
+    pub fn create() -> std::io::Result<Self> {
+        let dir = snapshot_dir().ok_or_else(not_found)?;
+        std::fs::create_dir_all(&dir)?;
+        let id = format!("{}-{}", std::process::id(), Local::now().timestamp_millis());
+        let lock_path = dir.join(format!("{}.lock", id));
+        let lock = File::create(&lock_path)?;
+        lock.try_lock().map_err(std::io::Error::from)?;
+        Ok(Self {
+            path: dir.join(format!("{}.json", id)),
+            lock_path,
+            _lock: lock,
+        })
+    }
+
+    /// スナップショットを保存する（書きかけのファイルが残らないよう一時ファイルから置き換える）
+    pub fn save(&self, snapshot: &Snapshot) -> std::io::Result<()> {
+        let json = serde_json::to_string(snapshot).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+
+    /// スナップショットを削除する
+    pub fn clear(&self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl Drop for SnapshotFile {
+    // スナップショットが残っていなければロック用のファイルも片付ける
+    fn drop(&mut self) {
+        if !self.path.exists() {
+            let _ = std::fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+/// 終了したプロセスが残した（中断した）セッションのスナップショット
+/// 確認している間はロックを持っておき、ほかのウィンドウが同じセッションを復元しないようにする。
+#[derive(Debug)]
+pub struct Orphan {
+    pub snapshot: Snapshot,
+    path: PathBuf,
+    lock_path: PathBuf,
+    _lock: File,
+}
+
+impl std::ops::Deref for Orphan {
+    type Target = Snapshot;
+
+    fn deref(&self) -> &Snapshot {
+        &self.snapshot
+    }
+}
+
+impl Orphan {
+    /// 再開・記録・破棄が済んだのでファイルを削除する
+    pub fn remove(self) {
+        let _ = std::fs::remove_file(&self.path);
+        let _ = std::fs::remove_file(&self.lock_path);
+    }
+}
+
+/// 中断したセッションのうち最後に保存されたものを探す（実行中のウィンドウのものは除く）
+pub fn find_orphan() -> Option<Orphan> {
+    let dir = snapshot_dir()?;
+    let mut orphans: Vec<Orphan> = std::fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            // ロックできなければ、そのスナップショットのウィンドウはまだ実行中
+            let lock_path = path.with_extension("lock");
+            let lock = File::create(&lock_path).ok()?;
+            lock.try_lock().ok()?;
+            let snapshot = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+            Some(Orphan {
+                snapshot,
+                path,
+                lock_path,
+                _lock: lock,
+            })
+        })
+        .collect();
+    orphans.sort_by_key(|o| o.saved_at);
+    orphans.pop()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    // 60 分前に始め、開始 10〜20 分に一時停止し、20 分前に保存したスナップショット
+    // （paused なら保存時には 30 分前から一時停止していた）
+    fn snapshot(paused: bool) -> Snapshot {
+        let now = Local::now();
+        let start = now - ChronoDuration::minutes(60);
+        Snapshot {
+            name: "a".to_owned(),
+            memo: "- [x] b\n- [ ] c".to_owned(),
+            memo_file: None,
+            start,
+            estimate_minutes: Some(90),
+            until: None,
+            pauses: vec![PauseInterval {
+                start: start + ChronoDuration::minutes(10),
+                end: start + ChronoDuration::minutes(20),
+                secs: 600,
+            }],
+            paused_since: paused.then(|| now - ChronoDuration::minutes(30)),
+            splits: BTreeMap::from([(0, 300)]),
+            split_items: vec!["b".to_owned()],
+            queue: Vec::new(),
+            pomodoro: None,
+            saved_at: now - ChronoDuration::minutes(20),
+        }
+    }
+
+    #[test]
+    fn elapsed_excludes_pauses() {
+        let snap = snapshot(false);
+        assert_eq!(snap.elapsed_secs(snap.saved_at), 30 * 60);
+        let snap = snapshot(true);
+        assert_eq!(snap.elapsed_secs(snap.saved_at), 20 * 60);
+    }
+
+    #[test]
+    fn record_ends_at_saved_time() {
+        let snap = snapshot(false);
+        let record = snap.to_record(&PomodoroConfig::default());
+        assert_eq!(record.end, snap.saved_at);
+        assert_eq!(record.elapsed_secs, 30 * 60);
+        assert_eq!(record.paused_secs, 600);
+        assert_eq!(record.deadline, Some(snap.start + ChronoDuration::minutes(90)));
+        assert_eq!(record.checklist[0].split_secs, Some(300));
+        assert!(!record.completed);
+    }
+
+    #[test]
+    fn record_closes_the_pause_at_saved_time() {
+        let snap = snapshot(true);
+        let record = snap.to_record(&PomodoroConfig::default());
+        assert_eq!(record.elapsed_secs, 20 * 60);
+        assert_eq!(record.pauses.len(), 2);
+        assert_eq!(record.pauses[1].end, snap.saved_at);
+        assert_eq!(record.paused_secs, 600 + 600);
+    }
+}
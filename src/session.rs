@@ -0,0 +1,907 @@
+// 作業セッション（ウィンドウと端末のどちらの画面からも使うタイマーとメモの状態）
+// 経過時間・〆・一時停止・チェックリスト・キュー・履歴やスナップショットの保存・フック・制御 API の処理をまとめる。
+// 画面ごとの状態（編集中かどうかなど）は持たない。
+use crate::{
+    config, control, history, hooks, markdown, memo_file, pause, pomodoro, queue, recovery,
+    splits, template, tr,
+};
+use chrono::Duration as ChronoDuration;
+use chrono::Local;
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// 1つの作業の計測状態
+pub struct Session {
+    pub name: String,
+    /// 計測を始めた時刻（一時停止していた時間も含む）
+    pub(crate) start: Instant,
+    /// 履歴に残すための開始時刻（壁時計）と所要見積時間[分]
+    pub(crate) start_local: chrono::DateTime<Local>,
+    pub(crate) estimate_minutes: Option<u64>,
+    /// 履歴へ書き込み済みかどうか（二重記録を防ぐ）
+    pub(crate) logged: bool,
+    /// 画面の下に表示するメッセージ（クリップボードへのコピー結果やエラー）
+    pub clip_msg: Option<String>,
+    pub memo: String,
+    /// optional end time (countdown) as local datetime for display
+    pub(crate) end_time_local: Option<chrono::DateTime<Local>>,
+    /// --until で〆の時刻が直接指定された場合は true（一時停止しても〆は動かない）
+    pub(crate) fixed_deadline: bool,
+    /// 中断したセッションを再開したとき、それまでの時間（壁時計での経過時間）
+    pub(crate) carried: Duration,
+    /// 履歴とスナップショットの保存（None なら保存しない）と、スナップショットを最後に保存した時刻、
+    /// 起動時に見つかった中断セッション
+    pub persistence: Option<Persistence>,
+    pub(crate) last_snapshot: Option<Instant>,
+    pub recovered: Option<recovery::Orphan>,
+    /// 一時停止の状態と一時停止していた区間
+    pub(crate) pauses: pause::PauseTracker,
+    /// チェックリスト項目ごとのチェック時刻
+    pub(crate) splits: splits::SplitTimes,
+    /// 完了後に続けて実行する作業
+    pub queue: VecDeque<queue::QueuedTask>,
+    /// 設定と、設定ファイルの再読み込み用の監視（読み込みエラーは画面に表示する）
+    pub config: config::Config,
+    pub config_watcher: Option<config::ConfigWatcher>,
+    pub config_error: Option<String>,
+    /// --clipboard で指定されたクリップボード出力（設定ファイルより優先）
+    pub clipboard_arg: Option<String>,
+    /// --memo-file で指定された、メモと同期する Markdown ファイル
+    pub memo_file: Option<memo_file::MemoFile>,
+    /// ポモドーロモードの場合、この作業を始めた時点のポモドーロの経過時間（キューの作業をまたいで続く）
+    pub pomodoro: Option<Duration>,
+    /// 前回確認したときのフェーズ（切り替わりの検出用）
+    pub(crate) pomodoro_phase: Option<(pomodoro::Phase, u32)>,
+    /// 設定ファイルのフックを実行するか
+    pub hooks_enabled: bool,
+    /// 完了時にクリップボードへコピーするか（StopwatchWidget がコピーする）
+    pub clipboard_enabled: bool,
+    /// 実行済みのフック
+    pub(crate) hook_tracker: hooks::HookTracker,
+    /// 制御 API のソケットと、ソケットのスレッドから届くリクエスト
+    pub(crate) control: Option<(control::Server, mpsc::Receiver<control::Incoming>)>,
+    /// 制御 API から「完了」が要求された（画面側で完了ボタンと同じ処理をする）
+    pub done_requested: bool,
+    /// 制御 API からオーバーレイ表示の切り替えが要求された（中身は Request::Overlay の enabled）
+    pub overlay_requested: Option<Option<bool>>,
+}
+
+/// 見積時間の上限[分]（7日）
+pub const MAX_ESTIMATE_MINUTES: u64 = 7 * 24 * 60;
+
+/// start の minutes 分後（minutes は上限で切り詰め、表せない時刻になる場合は start のまま）
+pub(crate) fn add_minutes(start: chrono::DateTime<Local>, minutes: u64) -> chrono::DateTime<Local> {
+    start
+        .checked_add_signed(ChronoDuration::minutes(minutes.min(MAX_ESTIMATE_MINUTES) as i64))
+        .unwrap_or(start)
+}
+
+/// 履歴ファイルへの記録と、中断に備えたスナップショットの保存（Session::persistence に設定したときだけ行う）
+#[derive(Debug, Default)]
+pub struct Persistence {
+    snapshot: Option<recovery::SnapshotFile>,
+}
+
+impl Persistence {
+    /// データディレクトリの履歴とスナップショットを使う（スナップショットを置けなければ履歴だけ）
+    pub fn open() -> Self {
+        let snapshot = match recovery::SnapshotFile::create() {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("{}", tr!("snapshot_save_failed", error = e));
+                None
+            }
+        };
+        Self { snapshot }
+    }
+}
+
+/// ウィンドウのタイトルの形式で使えるプレースホルダ
+pub const TITLE_FIELDS: &[&str] = &["time", "status", "name", "elapsed", "deadline"];
+
+/// 中断セッションの確認への答え
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryChoice {
+    /// 続きを計測する
+    Resume,
+    /// 最後に保存した時点で終わったものとして履歴に記録する
+    Record,
+    Discard,
+}
+
+/// poll で起きたことのうち、画面側で対応が必要なもの
+#[derive(Debug, Default)]
+pub struct PollResult {
+    /// 設定ファイルを読み込み直した（色などを反映し直す）
+    pub config_reloaded: bool,
+    /// ポモドーロのフェーズが切り替わった
+    pub phase_changed: bool,
+}
+
+impl Session {
+    /// 作業を始める。〆は until（〆の時刻）があればそれを、なければ end_minutes（所要見積時間[分]）から求める
+    pub fn new(
+        name: String,
+        memo_default: String,
+        end_minutes: Option<u64>,
+        until: Option<chrono::DateTime<Local>>,
+        queue: VecDeque<queue::QueuedTask>,
+        config: config::Config,
+    ) -> Self {
+        let start = Instant::now();
+        let start_local = Local::now();
+        // ライブラリから渡された見積時間は上限で切り詰める
+        let end_minutes = end_minutes.map(|m| m.min(MAX_ESTIMATE_MINUTES));
+        // compute optional end time（--until の指定があればそれを〆にする）
+        let end_time_local = until.or_else(|| end_minutes.map(|m| add_minutes(start_local, m)));
+
+        Self {
+            name,
+            start,
+            start_local,
+            estimate_minutes: end_minutes,
+            logged: false,
+            clip_msg: None,
+            memo: memo_default,
+            end_time_local,
+            fixed_deadline: until.is_some(),
+            carried: Duration::ZERO,
+            persistence: None,
+            last_snapshot: None,
+            recovered: None,
+            pauses: pause::PauseTracker::default(),
+            splits: splits::SplitTimes::default(),
+            queue,
+            config,
+            config_watcher: None,
+            config_error: None,
+            clipboard_arg: None,
+            memo_file: None,
+            pomodoro: None,
+            pomodoro_phase: None,
+            hooks_enabled: false,
+            clipboard_enabled: false,
+            hook_tracker: hooks::HookTracker::default(),
+            control: None,
+            done_requested: false,
+            overlay_requested: None,
+        }
+    }
+
+    /// キューの次の作業を開始する（キューが空なら false）
+    pub fn start_next(&mut self) -> bool {
+        let Some(task) = self.queue.pop_front() else {
+            return false;
+        };
+        let queue = std::mem::take(&mut self.queue);
+        let clip_msg = self.clip_msg.take();
+        let watcher = self.config_watcher.take();
+        let config_error = self.config_error.take();
+        let clipboard_arg = self.clipboard_arg.take();
+        let control = self.control.take();
+        let persistence = self.persistence.take();
+        let (hooks_enabled, clipboard_enabled) = (self.hooks_enabled, self.clipboard_enabled);
+        let pomodoro = self.pomodoro_clock(Instant::now());
+        let pomodoro_phase = self.pomodoro_phase;
+        *self = Session::new(task.name, task.memo, task.estimate, task.until, queue, self.config.clone());
+        // メモファイルが指定されていればその内容をメモにする（開けなければメッセージを表示する）
+        let mut clip_msg = clip_msg;
+        if let Some(path) = &task.memo_file {
+            match memo_file::MemoFile::open(path) {
+                Ok((file, memo)) => {
+                    self.memo_file = Some(file);
+                    self.memo = memo;
+                }
+                Err(e) => clip_msg = Some(e),
+            }
+        }
+        self.pomodoro = pomodoro;
+        self.pomodoro_phase = pomodoro_phase;
+        self.clip_msg = clip_msg;
+        self.config_watcher = watcher;
+        self.config_error = config_error;
+        self.clipboard_arg = clipboard_arg;
+        self.control = control;
+        self.persistence = persistence;
+        self.hooks_enabled = hooks_enabled;
+        self.clipboard_enabled = clipboard_enabled;
+        true
+    }
+
+    /// 一時停止中の時間を除いた経過時間
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        (now.saturating_duration_since(self.start) + self.carried)
+            .saturating_sub(self.pauses.paused_total(now))
+    }
+
+    /// ポモドーロの経過時間（ポモドーロモードでなければ None）
+    pub fn pomodoro_clock(&self, now: Instant) -> Option<Duration> {
+        self.pomodoro.map(|offset| offset + self.elapsed(now))
+    }
+
+    /// ポモドーロの現在のフェーズと、ポモドーロの経過時間
+    pub fn pomodoro_state(&self, now: Instant) -> Option<(pomodoro::PhaseState, Duration)> {
+        self.pomodoro_clock(now)
+            .map(|t| (pomodoro::phase_at(&self.config.pomodoro, t), t))
+    }
+
+    /// 所要見積時間（カウントダウンの長さ）
+    pub fn budget(&self) -> Option<Duration> {
+        self.estimate_minutes.map(|m| Duration::from_secs(m.saturating_mul(60)))
+    }
+
+    /// 〆の時刻。見積時間から求めた場合は一時停止した分だけ後ろにずらす（--until の時刻は動かさない）
+    pub fn deadline_local(&self, now: Instant) -> Option<chrono::DateTime<Local>> {
+        let end = self.end_time_local?;
+        if self.fixed_deadline {
+            return Some(end);
+        }
+        let paused = ChronoDuration::from_std(self.pauses.paused_total(now)).unwrap_or_default();
+        Some(end.checked_add_signed(paused).unwrap_or(end))
+    }
+
+    /// 〆までの残り秒数（超過していれば負）
+    pub fn remaining_secs(&self, now: Instant) -> Option<i64> {
+        if self.fixed_deadline {
+            return self.end_time_local.map(|t| (t - Local::now()).num_seconds());
+        }
+        self.budget()
+            .map(|b| b.as_secs() as i64 - self.elapsed(now).as_secs() as i64)
+    }
+
+    /// 背景色（一時停止中 → 休憩中 → 〆を超過 → 通常 の順に優先する）
+    pub fn background_color(&self, now: Instant) -> eframe::egui::Color32 {
+        let colors = &self.config.colors;
+        if self.pauses.is_paused() {
+            return colors.paused.0;
+        }
+        match self.pomodoro_state(now) {
+            Some((p, _)) if p.phase == pomodoro::Phase::LongBreak => return colors.long_break.0,
+            Some((p, _)) if p.phase == pomodoro::Phase::ShortBreak => return colors.short_break.0,
+            _ => {}
+        }
+        if self.remaining_secs(now).is_some_and(|r| r < 0) {
+            colors.overtime.0
+        } else {
+            colors.background.0
+        }
+    }
+
+    /// ウィンドウのタイトル（設定の title、なければ言語ごとの既定の形式。例: "12:34 残り — 作業名"）
+    pub fn title(&self, now: Instant) -> String {
+        let format = self.config.format;
+        let elapsed = format.duration(self.elapsed(now));
+        // 〆があれば残り時間（超過したら +超過時間）、なければ経過時間
+        let (time, status) = match self.remaining_secs(now) {
+            Some(r) if r >= 0 => (format.duration(Duration::from_secs(r as u64)), tr!("title_remaining")),
+            Some(r) => (
+                format!("+{}", format.duration(Duration::from_secs(r.unsigned_abs()))),
+                tr!("title_overtime"),
+            ),
+            None => (elapsed.clone(), tr!("title_elapsed")),
+        };
+        let status = if self.pauses.is_paused() { tr!("title_paused") } else { status };
+        let deadline = self
+            .deadline_local(now)
+            .map(|t| format.deadline(&t))
+            .unwrap_or_default();
+        let template = self.config.title.as_deref().unwrap_or(tr!("title"));
+        crate::i18n::fill(
+            template,
+            &[
+                ("time", time),
+                ("status", status.to_owned()),
+                ("name", self.name.clone()),
+                ("elapsed", elapsed),
+                ("deadline", deadline),
+            ],
+        )
+    }
+
+    /// 制御 API のソケットで待ち受ける（使えなければメッセージを出して、API なしで続ける）
+    /// wake はリクエストが届いたときに画面側を起こすために呼ぶ
+    pub fn listen(&mut self, wake: impl Fn() + Send + Sync + 'static) {
+        let (tx, rx) = mpsc::channel();
+        match control::serve(tx, wake) {
+            Ok(server) => self.control = Some((server, rx)),
+            Err(e) => eprintln!("{}", tr!("control_failed", error = e)),
+        }
+    }
+
+    /// 一定間隔で呼ぶ処理: 設定・メモファイルの再読み込み、制御 API、スナップショットの保存、フック。
+    /// memo_editing が true（メモの編集中）の間はメモファイルの変更を読み込まない。
+    pub fn poll(&mut self, memo_editing: bool) -> PollResult {
+        let mut result = PollResult::default();
+        // 設定ファイルが更新されていれば読み込み直す（エラー時は以前の設定のまま）
+        if let Some(reloaded) = self.config_watcher.as_mut().and_then(|w| w.poll()) {
+            match reloaded {
+                Ok(config) => {
+                    crate::i18n::set_language(config.language);
+                    self.config = config;
+                    self.config_error = None;
+                    result.config_reloaded = true;
+                    // フックの番号が変わるので、既に過ぎたイベントを実行済みにし直す
+                    let now = Instant::now();
+                    let (elapsed, remaining) = (self.elapsed(now).as_secs(), self.remaining_secs(now));
+                    self.hook_tracker.reset(&self.config.hooks, elapsed, remaining);
+                }
+                Err(e) => self.config_error = Some(e),
+            }
+        }
+
+        // メモファイルが外部で変更されていれば読み込み直す（編集中は手元の内容を優先）
+        if !memo_editing
+            && let Some(memo) = self.memo_file.as_mut().and_then(|f| f.poll())
+        {
+            self.memo = memo;
+        }
+
+        // 制御 API から届いたリクエストを処理して応答を返す
+        let requests: Vec<control::Incoming> = self
+            .control
+            .as_ref()
+            .map(|(_, rx)| rx.try_iter().collect())
+            .unwrap_or_default();
+        for (request, reply) in requests {
+            let response = self.handle_request(request);
+            let _ = reply.send(response);
+        }
+        // メモが書き換わっていれば、スプリットタイムを項目の新しい番号に付け替える（編集中は確定してから）
+        if !memo_editing {
+            self.sync_splits();
+        }
+
+        // 数秒ごとに実行中のセッションを保存する（中断セッションの確認中も、このウィンドウの分は保存する）
+        let snapshot_due = self
+            .last_snapshot
+            .is_none_or(|t| t.elapsed() >= Duration::from_secs(5));
+        if snapshot_due
+            && !self.logged
+            && let Some(file) = self.persistence.as_ref().and_then(|p| p.snapshot.as_ref())
+        {
+            if let Err(e) = file.save(&self.snapshot()) {
+                eprintln!("{}", tr!("snapshot_save_failed", error = e));
+            }
+            self.last_snapshot = Some(Instant::now());
+        }
+
+        // ポモドーロのフェーズの切り替わり
+        let now = Instant::now();
+        let current = self.pomodoro_state(now).map(|(p, _)| (p.phase, p.cycle));
+        result.phase_changed = self.pomodoro_phase.is_some() && current != self.pomodoro_phase;
+        self.pomodoro_phase = current;
+
+        // フック: 開始時と、見積時間の N%・〆・超過 N 分ごと
+        if self.hook_tracker.take_start() {
+            let start_hooks = self.hooks_for(hooks::HookEvent::Start);
+            self.run_hooks(&start_hooks, &[]);
+        }
+        let (elapsed, remaining) = (self.elapsed(now).as_secs(), self.remaining_secs(now));
+        let due = self.hook_tracker.due(&self.config.hooks, elapsed, remaining);
+        self.run_hooks(&due, &[]);
+        result
+    }
+
+    /// チェックの切り替えや編集の確定をメモファイルに書き戻す（メモの編集中は呼ばない）
+    pub fn sync_memo_file(&mut self) {
+        if let Some(file) = self.memo_file.as_mut()
+            && let Err(e) = file.write(&self.memo)
+        {
+            self.clip_msg = Some(tr!("memo_file_save_failed", error = e));
+        }
+    }
+
+    /// 中断に備えて保存するスナップショットを作る
+    pub fn snapshot(&self) -> recovery::Snapshot {
+        recovery::Snapshot {
+            name: self.name.clone(),
+            memo: self.memo.clone(),
+            memo_file: self.memo_file.as_ref().map(|f| f.path().to_path_buf()),
+            start: self.start_local,
+            estimate_minutes: self.estimate_minutes,
+            until: self.fixed_deadline.then_some(self.end_time_local).flatten(),
+            pauses: self.pauses.finished_intervals().to_vec(),
+            paused_since: self.pauses.paused_since(),
+            splits: self
+                .splits
+                .ticks()
+                .iter()
+                .map(|(&i, d)| (i, d.as_secs()))
+                .collect(),
+            split_items: self.splits.items().to_vec(),
+            queue: self.queue.iter().cloned().collect(),
+            pomodoro: self.pomodoro.map(|d| d.as_secs()),
+            saved_at: Local::now(),
+        }
+    }
+
+    /// 中断セッションの確認に答える
+    pub fn resolve_recovery(&mut self, choice: RecoveryChoice) {
+        let Some(orphan) = self.recovered.take() else {
+            return;
+        };
+        match choice {
+            RecoveryChoice::Resume => self.resume_snapshot(orphan.snapshot.clone()),
+            RecoveryChoice::Record => {
+                if let Err(e) = history::append(&orphan.to_record(&self.config.pomodoro)) {
+                    eprintln!("{}", tr!("history_save_failed", error = e));
+                }
+            }
+            RecoveryChoice::Discard => {}
+        }
+        // 再開した場合も、以後はこのウィンドウのスナップショットとして保存する
+        orphan.remove();
+        // 現在のセッションのスナップショットをすぐに保存し直す
+        self.last_snapshot = None;
+    }
+
+    // 中断したセッションを再開する（経過時間は壁時計の開始時刻から求め直す）
+    // 最後に保存してから再開するまで（中断していた間）は一時停止していたものとして扱う
+    fn resume_snapshot(&mut self, snap: recovery::Snapshot) {
+        let now = Instant::now();
+        let now_local = Local::now();
+        let split_items = snap.split_items();
+        let mut pauses = snap.pauses;
+        if snap.paused_since.is_none() && now_local > snap.saved_at {
+            pauses.push(pause::PauseInterval {
+                start: snap.saved_at,
+                end: now_local,
+                secs: (now_local - snap.saved_at).num_seconds().max(0) as u64,
+            });
+        }
+        self.name = snap.name;
+        self.memo = snap.memo;
+        // メモファイルと同期していた場合はファイルの内容を正とする
+        self.memo_file = None;
+        if let Some(path) = &snap.memo_file {
+            match memo_file::MemoFile::open(path) {
+                Ok((file, memo)) => {
+                    self.memo_file = Some(file);
+                    self.memo = memo;
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        self.start = now;
+        self.start_local = snap.start;
+        self.carried = (now_local - snap.start).to_std().unwrap_or_default();
+        self.estimate_minutes = snap.estimate_minutes.map(|m| m.min(MAX_ESTIMATE_MINUTES));
+        self.end_time_local = snap
+            .until
+            .or_else(|| self.estimate_minutes.map(|m| add_minutes(snap.start, m)));
+        self.fixed_deadline = snap.until.is_some();
+        self.pauses = pause::PauseTracker::restore(pauses, snap.paused_since, now);
+        self.splits = splits::SplitTimes::from_ticks(
+            snap.splits
+                .into_iter()
+                .map(|(i, secs)| (i, Duration::from_secs(secs)))
+                .collect(),
+            split_items,
+        );
+        self.sync_splits();
+        if self.queue.is_empty() {
+            self.queue = snap.queue.into();
+        }
+        self.pomodoro = snap.pomodoro.map(Duration::from_secs);
+        self.pomodoro_phase = None;
+        // 中断前に過ぎた〆などのフックはもう一度実行しない
+        let (elapsed, remaining) = (self.elapsed(now).as_secs(), self.remaining_secs(now));
+        self.hook_tracker.reset(&self.config.hooks, elapsed, remaining);
+        self.logged = false;
+    }
+
+    /// 現在のセッションの記録を作る
+    pub fn session_record(&self, completed: bool) -> history::SessionRecord {
+        let now = Instant::now();
+        history::SessionRecord {
+            name: self.name.clone(),
+            start: self.start_local,
+            end: Local::now(),
+            // 一時停止していた時間を除いた正味の作業時間
+            elapsed_secs: self.elapsed(now).as_secs(),
+            estimate_minutes: self.estimate_minutes,
+            deadline: self.deadline_local(now),
+            memo: self.memo.clone(),
+            checklist: self.checklist(),
+            paused_secs: self.pauses.paused_total(now).as_secs(),
+            pauses: self.pauses.intervals(now),
+            phases: match self.pomodoro {
+                Some(offset) => {
+                    pomodoro::phases_between(&self.config.pomodoro, offset, offset + self.elapsed(now))
+                }
+                None => Vec::new(),
+            },
+            completed,
+        }
+    }
+
+    /// メモのチェックリストと各項目の所要時間
+    pub fn checklist(&self) -> Vec<history::ChecklistItem> {
+        let mut checklist = history::checklist_from_memo(&self.memo);
+        // メモの編集中は記録の番号が古いままなので、今のメモに合わせたもので求める
+        let mut splits = self.splits.clone();
+        splits.sync(&checklist);
+        for (i, item) in checklist.iter_mut().enumerate() {
+            item.split_secs = splits.split(i).map(|d| d.as_secs());
+        }
+        checklist
+    }
+
+    /// スプリットタイムの記録を今のメモのチェックリストに合わせる
+    pub fn sync_splits(&mut self) {
+        self.splits.sync(&history::checklist_from_memo(&self.memo));
+    }
+
+    /// セッションの記録を履歴ファイルに追記する（1セッションにつき1回だけ。persistence がなければ記録しない）
+    pub fn record_session(&mut self, record: &history::SessionRecord) -> std::io::Result<()> {
+        if self.logged {
+            return Ok(());
+        }
+        let Some(persistence) = &self.persistence else {
+            self.logged = true;
+            return Ok(());
+        };
+        history::append(record)?;
+        self.logged = true;
+        // 正常に記録できたのでスナップショットは不要
+        if let Some(file) = &persistence.snapshot {
+            file.clear();
+        }
+        Ok(())
+    }
+
+    /// 完了: 履歴に記録して done フックを実行し、クリップボードに入れる文字列を返す
+    /// （コピーに失敗してもう一度呼んだときは、記録もフックも繰り返さない）
+    pub fn finish(&mut self) -> String {
+        let record = self.session_record(true);
+        if let Err(e) = self.record_session(&record) {
+            eprintln!("{}", tr!("history_save_failed", error = e));
+        }
+        if self.hook_tracker.take_done() {
+            let done_hooks = self.hooks_for(hooks::HookEvent::Done);
+            self.run_hooks(&done_hooks, &[]);
+        }
+        self.clipboard_text(&record)
+    }
+
+    // 指定した番号のフックを実行する（extra はイベントごとに追加する環境変数）
+    fn run_hooks(&mut self, which: &[usize], extra: &[(&str, String)]) {
+        if which.is_empty() || !self.hooks_enabled {
+            return;
+        }
+        let record = self.session_record(false);
+        let remaining = self.remaining_secs(Instant::now());
+        for &i in which {
+            let hook = &self.config.hooks[i];
+            let mut vars = hooks::env_vars(hook.event, &record, remaining);
+            if let Some(p) = hook.percent {
+                vars.push(("TSW_PERCENT".to_owned(), p.to_string()));
+            }
+            if hook.event == hooks::HookEvent::Overtime {
+                let over = remaining.map(|r| (-r).max(0) / 60).unwrap_or(0);
+                vars.push(("TSW_OVERTIME_MINUTES".to_owned(), over.to_string()));
+            }
+            vars.extend(extra.iter().map(|(k, v)| ((*k).to_owned(), v.clone())));
+            if let Err(e) = hooks::run(hook, &vars) {
+                eprintln!("{}", e);
+                self.clip_msg = Some(e);
+            }
+        }
+    }
+
+    // event のフックの番号
+    fn hooks_for(&self, event: hooks::HookEvent) -> Vec<usize> {
+        self.config
+            .hooks
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| h.event == event)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// 一時停止中かどうか
+    pub fn is_paused(&self) -> bool {
+        self.pauses.is_paused()
+    }
+
+    /// チェックリストの項目ごとのチェック時刻
+    pub fn splits(&self) -> &splits::SplitTimes {
+        &self.splits
+    }
+
+    /// 一時停止と再開を切り替える
+    pub fn toggle_pause(&mut self) {
+        self.pauses.toggle(Instant::now());
+    }
+
+    /// チェックリストの idx 番目の項目のチェックを切り替える（チェック時刻の記録とフックの実行も行う）
+    /// 切り替えた後のチェック状態を返す（項目がなければ None）
+    pub fn toggle_item(&mut self, idx: usize) -> Option<bool> {
+        self.sync_splits();
+        let (line, checked) = markdown::parse(&self.memo)
+            .into_iter()
+            .filter_map(|md| match md.kind {
+                markdown::LineKind::Task { checked } => Some((md.line, checked)),
+                _ => None,
+            })
+            .nth(idx)?;
+        // チェックの切り替えはその行だけを書き換える
+        self.memo = markdown::toggle_task(&self.memo, line);
+        if checked {
+            // チェックを外したら記録を消す
+            self.splits.untick(idx);
+            return Some(false);
+        }
+        self.splits.tick(idx, self.elapsed(Instant::now()));
+        // チェックを付けたらフックを実行する（項目と所要時間を渡す）
+        let item = history::checklist_from_memo(&self.memo)
+            .get(idx)
+            .map(|c| c.text.clone())
+            .unwrap_or_default();
+        let split = self.splits.split(idx).unwrap_or_default().as_secs();
+        let tick_hooks = self.hooks_for(hooks::HookEvent::Tick);
+        self.run_hooks(&tick_hooks, &[("TSW_ITEM", item), ("TSW_SPLIT_SECS", split.to_string())]);
+        Some(true)
+    }
+
+    /// 〆を minutes 分延ばす（〆がなければ今から minutes 分後を〆にする）
+    pub fn extend_deadline(&mut self, minutes: u64) {
+        let now = Instant::now();
+        let minutes = minutes.min(MAX_ESTIMATE_MINUTES);
+        match (self.end_time_local, self.fixed_deadline) {
+            (Some(end), true) => self.end_time_local = Some(add_minutes(end, minutes)),
+            _ => {
+                // 見積時間を延ばす。見積時間がなければ経過時間（分に切り上げ）からの見積にする
+                let base = self
+                    .estimate_minutes
+                    .unwrap_or_else(|| self.elapsed(now).as_secs().div_ceil(60));
+                let estimate = base.saturating_add(minutes).min(MAX_ESTIMATE_MINUTES);
+                self.estimate_minutes = Some(estimate);
+                self.end_time_local = Some(add_minutes(self.start_local, estimate));
+            }
+        }
+        // 〆が延びたので、〆・超過のフックはもう一度実行できるようにする
+        let (elapsed, remaining) = (self.elapsed(now).as_secs(), self.remaining_secs(now));
+        self.hook_tracker.reset(&self.config.hooks, elapsed, remaining);
+    }
+
+    /// 制御 API に返す現在の状態
+    pub fn status(&self) -> control::Status {
+        let now = Instant::now();
+        control::Status {
+            name: self.name.clone(),
+            start: self.start_local,
+            elapsed_secs: self.elapsed(now).as_secs(),
+            estimate_minutes: self.estimate_minutes,
+            deadline: self.deadline_local(now),
+            remaining_secs: self.remaining_secs(now),
+            paused: self.pauses.is_paused(),
+            memo: self.memo.clone(),
+            checklist: self.checklist(),
+            queue: self.queue.iter().map(|t| t.name.clone()).collect(),
+        }
+    }
+
+    // 制御 API のリクエストを処理する
+    fn handle_request(&mut self, request: control::Request) -> control::Response {
+        use control::Request;
+        let now = Instant::now();
+        match request {
+            Request::Status => {}
+            Request::Pause => {
+                if !self.pauses.is_paused() {
+                    self.pauses.pause(now);
+                }
+            }
+            Request::Resume => {
+                if self.pauses.is_paused() {
+                    self.pauses.resume(now);
+                }
+            }
+            Request::Done => self.done_requested = true,
+            Request::Overlay { enabled } => self.overlay_requested = Some(enabled),
+            Request::SetName { name } => self.name = name,
+            Request::AppendMemo { text } => {
+                let text = text.replace("<br/>", "\n").replace("<br>", "\n");
+                if !self.memo.is_empty() {
+                    self.memo.push('\n');
+                }
+                self.memo.push_str(&text);
+            }
+            Request::Tick { item } => {
+                if item == 0 {
+                    return control::Response::error(tr!("item_number_invalid", value = item));
+                }
+                let checked = history::checklist_from_memo(&self.memo)
+                    .get(item - 1)
+                    .map(|c| c.checked);
+                match checked {
+                    None => return control::Response::error(tr!("item_missing", item = item)),
+                    Some(true) => {}
+                    Some(false) => {
+                        self.toggle_item(item - 1);
+                    }
+                }
+            }
+            Request::Extend { minutes } => {
+                if !(1..=MAX_ESTIMATE_MINUTES).contains(&minutes) {
+                    return control::Response::error(tr!(
+                        "extend_out_of_range",
+                        max = MAX_ESTIMATE_MINUTES,
+                        value = minutes,
+                    ));
+                }
+                self.extend_deadline(minutes);
+            }
+            Request::Open { tasks, replace } => {
+                if tasks.is_empty() {
+                    return control::Response::error(tr!("open_no_tasks"));
+                }
+                if let Err(e) = tasks.iter().try_for_each(queue::QueuedTask::validate) {
+                    return control::Response::error(e);
+                }
+                if replace {
+                    // 渡された作業をキューの先頭に入れ、今の作業を（未完了として）記録して次に進む
+                    for task in tasks.into_iter().rev() {
+                        self.queue.push_front(task);
+                    }
+                    let record = self.session_record(false);
+                    if let Err(e) = self.record_session(&record) {
+                        eprintln!("{}", tr!("history_save_failed", error = e));
+                    }
+                    self.start_next();
+                } else {
+                    self.queue.extend(tasks);
+                }
+            }
+        }
+        control::Response {
+            ok: true,
+            error: None,
+            status: Some(self.status()),
+        }
+    }
+
+    // 完了時にクリップボードへ入れる文字列（テンプレートは --clipboard または設定ファイルで選ぶ）
+    fn clipboard_text(&self, record: &history::SessionRecord) -> String {
+        let template = self
+            .config
+            .clipboard
+            .resolve(self.clipboard_arg.as_deref())
+            .or_else(|_| template::resolve(template::DEFAULT_PRESET, &Default::default()))
+            .expect("組み込みのプリセットは常に解釈できる");
+        template.render(record, &self.config.format)
+    }
+}
+
+/// 文字列をクリップボードにコピーする（画面に表示するメッセージを返す。メッセージには1行目だけを入れる）
+pub fn copy_to_clipboard(text: &str) -> Result<String, String> {
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut cb = arboard::Clipboard::new().map_err(|e| tr!("clipboard_open_failed", error = format!("{:?}", e)))?;
+    cb.set_text(text.to_owned())
+        .map_err(|e| tr!("clipboard_copy_failed", error = format!("{:?}", e)))?;
+    Ok(tr!("clipboard_copied", text = first_line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pause::PauseInterval;
+
+    fn session() -> Session {
+        Session::new("a".to_owned(), String::new(), None, None, VecDeque::new(), config::Config::default())
+    }
+
+    // 60 分前に始め、開始 10〜20 分に一時停止し、20 分前に保存したスナップショット
+    // （paused なら保存時には 30 分前から一時停止していた）
+    fn snapshot(paused: bool) -> recovery::Snapshot {
+        let now = Local::now();
+        let start = now - ChronoDuration::minutes(60);
+        recovery::Snapshot {
+            name: "b".to_owned(),
+            memo: String::new(),
+            memo_file: None,
+            start,
+            estimate_minutes: Some(90),
+            until: None,
+            pauses: vec![PauseInterval {
+                start: start + ChronoDuration::minutes(10),
+                end: start + ChronoDuration::minutes(20),
+                secs: 600,
+            }],
+            paused_since: paused.then(|| now - ChronoDuration::minutes(30)),
+            splits: Default::default(),
+            split_items: Vec::new(),
+            queue: Vec::new(),
+            pomodoro: None,
+            saved_at: now - ChronoDuration::minutes(20),
+        }
+    }
+
+    // 秒の切り捨てでずれる分は許す
+    fn assert_near(actual: i64, expected: i64) {
+        assert!((actual - expected).abs() <= 1, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn resume_counts_the_gap_as_paused() {
+        let snap = snapshot(false);
+        let start = snap.start;
+        let mut s = session();
+        s.resume_snapshot(snap);
+        let now = Instant::now();
+        assert_eq!(s.name, "b");
+        assert!(!s.is_paused());
+        // 60 分 - 一時停止 10 分 - 中断していた 20 分
+        assert_near(s.elapsed(now).as_secs() as i64, 30 * 60);
+        assert_near(s.remaining_secs(now).unwrap(), 60 * 60);
+        let deadline = s.deadline_local(now).unwrap();
+        assert_near((deadline - start).num_seconds(), 120 * 60);
+    }
+
+    #[test]
+    fn resume_paused_snapshot_stays_paused() {
+        let snap = snapshot(true);
+        let start = snap.start;
+        let mut s = session();
+        s.resume_snapshot(snap);
+        let now = Instant::now();
+        assert!(s.is_paused());
+        // 中断していた間は保存時から続く一時停止に含まれるので、二重に差し引かない
+        assert_near(s.elapsed(now).as_secs() as i64, 20 * 60);
+        let deadline = s.deadline_local(now).unwrap();
+        assert_near((deadline - start).num_seconds(), 130 * 60);
+        s.toggle_pause();
+        let intervals = s.pauses.intervals(Instant::now());
+        assert_eq!(intervals.len(), 2);
+        assert_near(intervals[1].secs as i64, 30 * 60);
+    }
+
+    #[test]
+    fn huge_estimates_are_clamped() {
+        let mut s = Session::new("a".to_owned(), String::new(), Some(u64::MAX), None, VecDeque::new(), config::Config::default());
+        assert_eq!(s.estimate_minutes, Some(MAX_ESTIMATE_MINUTES));
+        s.extend_deadline(u64::MAX);
+        assert_eq!(s.estimate_minutes, Some(MAX_ESTIMATE_MINUTES));
+        let deadline = s.deadline_local(Instant::now()).unwrap();
+        assert_eq!((deadline - s.start_local).num_minutes(), MAX_ESTIMATE_MINUTES as i64);
+    }
+
+    #[test]
+    fn control_tick_checks_only_existing_items() {
+        let mut s = session();
+        s.memo = "- [ ] a\n- [x] b".to_owned();
+        let response = s.handle_request(control::Request::Tick { item: 0 });
+        assert!(!response.ok);
+        assert_eq!(response.error, Some(tr!("item_number_invalid", value = 0)));
+        let response = s.handle_request(control::Request::Tick { item: 3 });
+        assert!(!response.ok);
+        assert_eq!(response.error, Some(tr!("item_missing", item = 3)));
+        assert_eq!(s.memo, "- [ ] a\n- [x] b");
+
+        assert!(s.handle_request(control::Request::Tick { item: 1 }).ok);
+        assert_eq!(s.memo, "- [x] a\n- [x] b");
+        // チェック済みの項目はそのまま（チェックを外さない）
+        assert!(s.handle_request(control::Request::Tick { item: 2 }).ok);
+        assert_eq!(s.memo, "- [x] a\n- [x] b");
+    }
+
+    #[test]
+    fn control_extend_rejects_out_of_range_minutes() {
+        let mut s = Session::new("a".to_owned(), String::new(), Some(30), None, VecDeque::new(), config::Config::default());
+        for minutes in [0, MAX_ESTIMATE_MINUTES + 1] {
+            let response = s.handle_request(control::Request::Extend { minutes });
+            assert!(!response.ok);
+            assert_eq!(
+                response.error,
+                Some(tr!("extend_out_of_range", max = MAX_ESTIMATE_MINUTES, value = minutes))
+            );
+            assert_eq!(s.estimate_minutes, Some(30));
+        }
+
+        assert!(s.handle_request(control::Request::Extend { minutes: 10 }).ok);
+        assert_eq!(s.estimate_minutes, Some(40));
+    }
+}
@@ -0,0 +1,166 @@
+// チェックリスト項目ごとのスプリットタイム
+// チェックを付けた時点の経過時間（一時停止分を除く）を項目ごとに記録し、
+// 直前のチェックからの所要時間をその項目の所要時間とする。
+// メモが書き換わって項目の番号が変わったときは、項目の文言をもとに記録を付け替える。
+use crate::history::ChecklistItem;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone)]
+pub struct SplitTimes {
+    // チェックリスト項目の番号（メモ中で何番目のチェックボックスか） -> チェック時の経過時間
+    ticks: BTreeMap<usize, Duration>,
+    // ticks の番号が指しているチェックリスト項目の文言
+    items: Vec<String>,
+}
+
+impl SplitTimes {
+    /// 保存しておいたチェック時刻と、そのときのチェックリスト項目の文言から復元する
+    pub fn from_ticks(ticks: BTreeMap<usize, Duration>, items: Vec<String>) -> Self {
+        Self { ticks, items }
+    }
+
+    pub fn ticks(&self) -> &BTreeMap<usize, Duration> {
+        &self.ticks
+    }
+
+    /// 記録の番号が指しているチェックリスト項目の文言
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// メモの今のチェックリストに合わせて、記録を項目の新しい番号に付け替える。
+    /// 同じ文言の項目は出てくる順に対応させ、チェックが外れた項目の記録は消す。
+    /// 文言が見つからない記録（項目を書き換えた場合）は、記録のないチェック済みの項目に順に対応させる。
+    pub fn sync(&mut self, checklist: &[ChecklistItem]) {
+        let same = self.items.len() == checklist.len()
+            && self.items.iter().zip(checklist).all(|(old, new)| *old == new.text);
+        if same {
+            self.ticks.retain(|&i, _| checklist[i].checked);
+            return;
+        }
+        let mut taken = vec![false; checklist.len()];
+        let mut ticks = BTreeMap::new();
+        let mut unmatched = Vec::new();
+        for (&i, &at) in &self.ticks {
+            let Some(text) = self.items.get(i) else {
+                continue;
+            };
+            let nth = self.items[..i].iter().filter(|t| *t == text).count();
+            let found = checklist
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| item.text == *text)
+                .nth(nth)
+                .map(|(j, _)| j);
+            match found {
+                Some(j) => {
+                    if checklist[j].checked {
+                        taken[j] = true;
+                        ticks.insert(j, at);
+                    }
+                }
+                None => unmatched.push(at),
+            }
+        }
+        let free: Vec<usize> = (0..checklist.len())
+            .filter(|&j| checklist[j].checked && !taken[j])
+            .collect();
+        for (j, at) in free.into_iter().zip(unmatched) {
+            ticks.insert(j, at);
+        }
+        self.ticks = ticks;
+        self.items = checklist.iter().map(|item| item.text.clone()).collect();
+    }
+
+    pub fn tick(&mut self, index: usize, elapsed: Duration) {
+        self.ticks.insert(index, elapsed);
+    }
+
+    pub fn untick(&mut self, index: usize) {
+        self.ticks.remove(&index);
+    }
+
+    /// 最後にチェックを付けた時点の経過時間（まだ無ければ 0）
+    pub fn last_tick(&self) -> Duration {
+        self.ticks.values().copied().max().unwrap_or_default()
+    }
+
+    /// 項目 index をチェックした時点の経過時間
+    pub fn ticked_at(&self, index: usize) -> Option<Duration> {
+        self.ticks.get(&index).copied()
+    }
+
+    /// 項目 index の所要時間（その直前にチェックされた項目、または開始からの時間）
+    pub fn split(&self, index: usize) -> Option<Duration> {
+        let at = self.ticked_at(index)?;
+        let prev = self
+            .ticks
+            .iter()
+            .filter(|&(&i, &t)| t < at || (t == at && i < index))
+            .map(|(_, &t)| t)
+            .max()
+            .unwrap_or_default();
+        Some(at - prev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(list: &[(&str, bool)]) -> Vec<ChecklistItem> {
+        list.iter()
+            .map(|&(text, checked)| ChecklistItem {
+                text: text.to_owned(),
+                checked,
+                split_secs: None,
+            })
+            .collect()
+    }
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn split_is_time_since_previous_tick() {
+        let mut splits = SplitTimes::default();
+        splits.tick(0, secs(60));
+        splits.tick(2, secs(150));
+        assert_eq!(splits.split(0), Some(secs(60)));
+        assert_eq!(splits.split(2), Some(secs(90)));
+        assert_eq!(splits.split(1), None);
+        assert_eq!(splits.last_tick(), secs(150));
+    }
+
+    #[test]
+    fn sync_follows_inserted_items() {
+        let mut splits = SplitTimes::default();
+        splits.sync(&items(&[("a", true), ("b", false)]));
+        splits.tick(0, secs(60));
+        // 先頭に項目を足しても、記録は "a" に付いたまま
+        splits.sync(&items(&[("new", false), ("a", true), ("b", false)]));
+        assert_eq!(splits.ticked_at(0), None);
+        assert_eq!(splits.ticked_at(1), Some(secs(60)));
+    }
+
+    #[test]
+    fn sync_keeps_duplicates_in_order_and_drops_unchecked() {
+        let mut splits = SplitTimes::from_ticks(
+            [(0, secs(10)), (1, secs(20))].into(),
+            vec!["x".to_owned(), "x".to_owned()],
+        );
+        splits.sync(&items(&[("y", false), ("x", true), ("x", false)]));
+        assert_eq!(splits.ticked_at(1), Some(secs(10)));
+        assert_eq!(splits.ticked_at(2), None);
+        assert_eq!(splits.ticks().len(), 1);
+    }
+
+    #[test]
+    fn sync_moves_edited_item_to_checked_item_without_record() {
+        let mut splits = SplitTimes::from_ticks([(0, secs(30))].into(), vec!["draft".to_owned()]);
+        splits.sync(&items(&[("first", false), ("draft v2", true)]));
+        assert_eq!(splits.ticked_at(1), Some(secs(30)));
+    }
+}
@@ -0,0 +1,269 @@
+// クリップボード出力のテンプレート
+// `{name}` や `{start:%H%M}` のようなプレースホルダをセッションの値で置き換える。
+// `{{` / `}}` はそれぞれ `{` / `}` そのものを表す。
+//
+//   {name}          作業名
+//   {memo}          メモ全文
+//   {start[:書式]}  開始時刻（書式は chrono の strftime 形式、既定は %H%M）
+//   {end[:書式]}    終了時刻（同上）
+//   {elapsed}       経過時間（設定の [format] の形式。既定は mm:ss、1時間以上は h:mm:ss）
+//   {elapsed_min}   経過時間[分]
+//   {elapsed_sec}   経過時間[秒]
+//   {estimate}      所要見積時間[分]（無ければ空）
+//   {diff_min}      経過時間 - 見積時間[分]（符号付き、見積が無ければ空）
+//   {checked}       チェック済みの項目数
+//   {total}         チェックリストの項目数
+//   {checklist}     チェックリスト（"- [x] 項目" を1行ずつ）
+//   {splits}        チェック済み項目の所要時間（"- 項目 mm:ss" を1行ずつ。時間の形式は {elapsed} と同じ）
+use crate::history::SessionRecord;
+use crate::format::Format;
+use crate::tr;
+use chrono::format::{Item, StrftimeItems};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// 組み込みのプリセット（名前, テンプレート）。summary は表示する言語のカタログから引く
+pub fn presets() -> [(&'static str, &'static str); 6] {
+    [
+        // 従来どおり: 完了時刻のみ
+        ("time", "{end:%H%M}"),
+        // 完了時刻 + ステップごとの所要時間
+        ("splits", "{end:%H%M}\n{splits}"),
+        ("range", "{start:%H%M}-{end:%H%M}"),
+        ("elapsed", "{elapsed_min}"),
+        ("summary", tr!("preset_summary")),
+        ("checklist", "{name} {checked}/{total}\n{checklist}"),
+    ]
+}
+
+/// 既定のプリセット名
+pub const DEFAULT_PRESET: &str = "time";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field(String, Option<String>),
+}
+
+const FIELDS: &[&str] = &[
+    "name",
+    "memo",
+    "start",
+    "end",
+    "elapsed",
+    "elapsed_min",
+    "elapsed_sec",
+    "estimate",
+    "diff_min",
+    "checked",
+    "total",
+    "checklist",
+    "splits",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = src.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => return Err(tr!("template_unclosed", template = format!("{:?}", src))),
+                        }
+                    }
+                    let (key, fmt) = match inner.split_once(':') {
+                        Some((k, f)) => (k.trim().to_owned(), Some(f.to_owned())),
+                        None => (inner.trim().to_owned(), None),
+                    };
+                    if !FIELDS.contains(&key.as_str()) {
+                        return Err(tr!("template_unknown_field", field = key));
+                    }
+                    if let Some(f) = &fmt {
+                        if key != "start" && key != "end" {
+                            return Err(tr!("template_no_format", field = key));
+                        }
+                        check_time_format(&key, f)?;
+                    }
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(key, fmt));
+                }
+                '}' => return Err(tr!("template_unmatched", template = format!("{:?}", src))),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+
+    /// セッションの記録からクリップボードに入れる文字列を作る（末尾の空白・改行は取り除く）
+    pub fn render(&self, record: &SessionRecord, format: &Format) -> String {
+        let mut out = String::new();
+        for seg in &self.segments {
+            match seg {
+                Segment::Text(t) => out.push_str(t),
+                Segment::Field(key, fmt) => out.push_str(&field(record, key, fmt.as_deref(), format)),
+            }
+        }
+        out.trim_end().to_owned()
+    }
+}
+
+// 時刻の書式が chrono の strftime 形式として解釈できるか（解釈できない書式は出力時にパニックする）
+fn check_time_format(key: &str, fmt: &str) -> Result<(), String> {
+    if StrftimeItems::new(fmt).any(|item| item == Item::Error) {
+        return Err(tr!("template_bad_time_format", field = key, format = format!("{:?}", fmt)));
+    }
+    Ok(())
+}
+
+fn field(r: &SessionRecord, key: &str, fmt: Option<&str>, format: &Format) -> String {
+    let elapsed_min = r.elapsed_secs / 60;
+    match key {
+        "name" => r.name.clone(),
+        "memo" => r.memo.clone(),
+        "start" => r.start.format(fmt.unwrap_or("%H%M")).to_string(),
+        "end" => r.end.format(fmt.unwrap_or("%H%M")).to_string(),
+        "elapsed" => format.duration(Duration::from_secs(r.elapsed_secs)),
+        "elapsed_min" => elapsed_min.to_string(),
+        "elapsed_sec" => r.elapsed_secs.to_string(),
+        "estimate" => r.estimate_minutes.map(|m| m.to_string()).unwrap_or_default(),
+        "diff_min" => r
+            .estimate_minutes
+            .map(|m| format!("{:+}", elapsed_min as i64 - m as i64))
+            .unwrap_or_default(),
+        "checked" => r.checklist.iter().filter(|c| c.checked).count().to_string(),
+        "total" => r.checklist.len().to_string(),
+        "checklist" => r
+            .checklist
+            .iter()
+            .map(|c| format!("- [{}] {}", if c.checked { "x" } else { " " }, c.text))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "splits" => r
+            .checklist
+            .iter()
+            .filter_map(|c| {
+                c.split_secs
+                    .map(|s| format!("- {} {}", c.text, format.duration(Duration::from_secs(s))))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// プリセット名（ユーザー定義 → 組み込みの順に探す）またはテンプレート文字列からテンプレートを作る
+pub fn resolve(spec: &str, user_presets: &BTreeMap<String, String>) -> Result<Template, String> {
+    if let Some(t) = user_presets.get(spec) {
+        return Template::parse(t);
+    }
+    if let Some((_, t)) = presets().into_iter().find(|(name, _)| *name == spec) {
+        return Template::parse(t);
+    }
+    if !spec.contains('{') && spec.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(tr!("preset_unknown", name = format!("{:?}", spec)));
+    }
+    // `<br>` は引数のメモと同様に改行として扱う
+    Template::parse(&spec.replace("<br/>", "\n").replace("<br>", "\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ChecklistItem;
+    use chrono::{Local, TimeZone};
+
+    fn record() -> SessionRecord {
+        SessionRecord {
+            name: "作業".to_owned(),
+            start: Local.with_ymd_and_hms(2024, 5, 1, 9, 5, 0).unwrap(),
+            end: Local.with_ymd_and_hms(2024, 5, 1, 9, 35, 30).unwrap(),
+            elapsed_secs: 1530,
+            estimate_minutes: Some(30),
+            deadline: None,
+            memo: "- [x] A\n- [ ] B".to_owned(),
+            checklist: vec![
+                ChecklistItem { text: "A".to_owned(), checked: true, split_secs: Some(90) },
+                ChecklistItem { text: "B".to_owned(), checked: false, split_secs: None },
+            ],
+            paused_secs: 0,
+            pauses: Vec::new(),
+            phases: Vec::new(),
+            completed: true,
+        }
+    }
+
+    fn render(src: &str) -> String {
+        Template::parse(src).unwrap().render(&record(), &Format::default())
+    }
+
+    #[test]
+    fn parse_splits_text_and_fields() {
+        let t = Template::parse("a{name}b{start:%H}").unwrap();
+        assert_eq!(
+            t.segments,
+            vec![
+                Segment::Text("a".to_owned()),
+                Segment::Field("name".to_owned(), None),
+                Segment::Text("b".to_owned()),
+                Segment::Field("start".to_owned(), Some("%H".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_templates() {
+        assert!(Template::parse("{name").is_err());
+        assert!(Template::parse("name}").is_err());
+        assert!(Template::parse("{unknown}").is_err());
+        assert!(Template::parse("{elapsed:%H}").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_invalid_time_format() {
+        assert!(Template::parse("{end:%Q}").is_err());
+        assert!(Template::parse("{start:%}").is_err());
+        assert!(Template::parse("{start:%Y-%m-%d %H:%M}").is_ok());
+    }
+
+    #[test]
+    fn render_fields() {
+        assert_eq!(render("{{{name}}}"), "{作業}");
+        assert_eq!(render("{start}-{end:%H:%M}"), "0905-09:35");
+        assert_eq!(render("{elapsed} {elapsed_min} {elapsed_sec}"), "25:30 25 1530");
+        assert_eq!(render("{estimate} {diff_min}"), "30 -5");
+        assert_eq!(render("{checked}/{total}\n{checklist}"), "1/2\n- [x] A\n- [ ] B");
+        assert_eq!(render("{splits}\n\n"), "- A 01:30");
+    }
+
+    #[test]
+    fn default_preset_is_end_time_only() {
+        let presets = BTreeMap::new();
+        let render = |spec| resolve(spec, &presets).unwrap().render(&record(), &Format::default());
+        assert_eq!(render(DEFAULT_PRESET), "0935");
+        assert_eq!(render("splits"), "0935\n- A 01:30");
+    }
+}
@@ -0,0 +1,442 @@
+// 端末用の画面（--tui）
+// ディスプレイのない環境（SSH 先など）向けに、ウィンドウと同じ〆・経過時間・作業名・チェックリストを端末に表示する。
+// タイマーやメモの処理はウィンドウと同じ Session を使う。
+//
+// キー操作:
+//   ↑↓ / k j   チェックリストの項目を選ぶ      Space / Enter   チェックを切り替える
+//   p          一時停止 / 再開                  d               完了
+//   n          作業名を変更                      a               メモに行を追加
+//   e          〆を延ばす                        q / Ctrl+C      終了（未完了として記録）
+use crate::cli;
+use taskstopwatch::session::{self, RecoveryChoice, Session};
+use taskstopwatch::{history, markdown, tr};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// 画面下の1行入力で何を入力しているか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Name,
+    Memo,
+    Extend,
+}
+
+impl Prompt {
+    fn label(self) -> &'static str {
+        match self {
+            Prompt::Name => tr!("tui_prompt_name"),
+            Prompt::Memo => tr!("tui_prompt_memo"),
+            Prompt::Extend => tr!("tui_prompt_extend"),
+        }
+    }
+}
+
+struct Tui {
+    session: Session,
+    // 選択中のチェックリスト項目（0始まり）
+    selected: usize,
+    // 1行入力の種類と入力中の文字列
+    prompt: Option<(Prompt, String)>,
+}
+
+// 端末を元の状態に戻す（パニックしたときも戻るように Drop で行う）
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// 完了したときの後処理（クリップボードに入れられなかった場合は端末に出力する）
+enum Exit {
+    Quit,
+    Done { text: String, copied: bool },
+}
+
+/// 端末の画面で実行する
+pub fn run(session: Session) -> io::Result<()> {
+    let mut tui = Tui {
+        session,
+        selected: 0,
+        prompt: None,
+    };
+    let exit = {
+        let _guard = TerminalGuard::enter()?;
+        tui.main_loop()?
+    };
+    match exit {
+        Exit::Quit => {
+            let record = tui.session.session_record(false);
+            if let Err(e) = tui.session.record_session(&record) {
+                eprintln!("{}", tr!("history_save_failed", error = e));
+            }
+        }
+        // SSH 先などでクリップボードが使えないときは、代わりに端末へ出力する
+        Exit::Done { text, copied: false } => println!("{}", text),
+        Exit::Done { .. } => {}
+    }
+    Ok(())
+}
+
+fn rgb(c: eframe::egui::Color32) -> Color {
+    Color::Rgb {
+        r: c.r(),
+        g: c.g(),
+        b: c.b(),
+    }
+}
+
+impl Tui {
+    fn main_loop(&mut self) -> io::Result<Exit> {
+        let mut out = io::stdout();
+        loop {
+            let polled = self.session.poll(false);
+            // ポモドーロのフェーズが切り替わったらベルを鳴らす
+            if polled.phase_changed {
+                queue!(out, Print("\x07"))?;
+            }
+            if self.session.done_requested {
+                self.session.done_requested = false;
+                if let Some(exit) = self.finish() {
+                    return Ok(exit);
+                }
+            }
+            self.draw(&mut out)?;
+            let interval = Duration::from_millis(self.session.config.repaint_interval_ms);
+            if event::poll(interval)?
+                && let Event::Key(key) = event::read()?
+                && key.kind != KeyEventKind::Release
+                && let Some(exit) = self.on_key(key)
+            {
+                return Ok(exit);
+            }
+            self.session.sync_memo_file();
+        }
+    }
+
+    // 完了: 記録してクリップボードに入れ、キューに次の作業があれば続ける
+    fn finish(&mut self) -> Option<Exit> {
+        let text = self.session.finish();
+        let copied = session::copy_to_clipboard(&text);
+        if !self.session.start_next() {
+            return Some(Exit::Done {
+                text,
+                copied: copied.is_ok(),
+            });
+        }
+        self.selected = 0;
+        self.session.clip_msg = Some(copied.unwrap_or_else(|e| e));
+        None
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Option<Exit> {
+        // Ctrl+D などを d（完了）と取り違えないよう、Ctrl+C 以外の Ctrl の組み合わせは無視する
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return (key.code == KeyCode::Char('c')).then_some(Exit::Quit);
+        }
+        if let Some((kind, mut input)) = self.prompt.take() {
+            match key.code {
+                KeyCode::Enter => self.submit(kind, input),
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.prompt = Some((kind, input));
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.prompt = Some((kind, input));
+                }
+                _ => self.prompt = Some((kind, input)),
+            }
+            return None;
+        }
+        // 中断セッションの確認中
+        if self.session.recovered.is_some() {
+            let choice = match key.code {
+                KeyCode::Char('r') => Some(RecoveryChoice::Resume),
+                KeyCode::Char('l') => Some(RecoveryChoice::Record),
+                KeyCode::Char('x') => Some(RecoveryChoice::Discard),
+                _ => None,
+            };
+            if let Some(choice) = choice {
+                self.session.resolve_recovery(choice);
+                self.selected = 0;
+                return None;
+            }
+        }
+        let items = history::checklist_from_memo(&self.session.memo).len();
+        match key.code {
+            KeyCode::Char('q') => return Some(Exit::Quit),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(items.saturating_sub(1));
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                self.session.toggle_item(self.selected);
+            }
+            KeyCode::Char('p') => self.session.toggle_pause(),
+            KeyCode::Char('d') => return self.finish(),
+            KeyCode::Char('n') => self.prompt = Some((Prompt::Name, self.session.name.clone())),
+            KeyCode::Char('a') => self.prompt = Some((Prompt::Memo, String::new())),
+            KeyCode::Char('e') => self.prompt = Some((Prompt::Extend, String::new())),
+            _ => {}
+        }
+        None
+    }
+
+    // 1行入力の確定
+    fn submit(&mut self, kind: Prompt, input: String) {
+        match kind {
+            Prompt::Name => {
+                if !input.trim().is_empty() {
+                    self.session.name = input;
+                }
+            }
+            Prompt::Memo => {
+                if !input.is_empty() {
+                    if !self.session.memo.is_empty() {
+                        self.session.memo.push('\n');
+                    }
+                    self.session.memo.push_str(&input);
+                }
+            }
+            Prompt::Extend => match cli::parse_estimate(&input) {
+                Ok(minutes) if minutes > 0 => self.session.extend_deadline(minutes),
+                Ok(_) => self.session.clip_msg = Some(tr!("extend_too_short").to_owned()),
+                Err(e) => self.session.clip_msg = Some(e),
+            },
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let s = &self.session;
+        let colors = &s.config.colors;
+        let format = s.config.format;
+        let now = Instant::now();
+        let elapsed = s.elapsed(now);
+        let remaining = s.remaining_secs(now);
+        let is_paused = s.is_paused();
+        let phase = s.pomodoro_state(now);
+
+        // 見出し部分の背景色はウィンドウと同じ規則で決める
+        let bg = rgb(s.background_color(now));
+        let width = terminal::size().map(|(w, _)| w).ok().filter(|&w| w > 0).unwrap_or(80);
+        let width = width as usize;
+
+        queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+
+        if let Some(snap) = &s.recovered {
+            let snap_secs = snap.elapsed_secs(snap.saved_at);
+            queue!(
+                out,
+                Print(tr!(
+                    "recovery_prompt",
+                    name = snap.name,
+                    start = format.date_time(&snap.start),
+                    elapsed = format.duration(Duration::from_secs(snap_secs)),
+                )),
+                Print(format!("\r\n  {}\r\n\r\n", tr!("tui_recovery_keys"))),
+            )?;
+        }
+
+        if let Some((p, t)) = phase {
+            header(
+                out,
+                width,
+                bg,
+                format!(
+                    " {}",
+                    tr!(
+                        "pomodoro_status",
+                        phase = p.phase.label(),
+                        cycle = p.cycle,
+                        cycles = s.config.pomodoro.cycles,
+                        time = format.duration(p.end.saturating_sub(t)),
+                    )
+                ),
+                rgb(colors.text.0),
+            )?;
+        }
+        if let Some(end) = s.deadline_local(now) {
+            let rest = match remaining {
+                Some(r) if r >= 0 => tr!("remaining", time = format.duration(Duration::from_secs(r as u64))),
+                Some(r) => tr!("overtime", time = format.duration(Duration::from_secs(r.unsigned_abs()))),
+                None => String::new(),
+            };
+            header(out, width, bg, format!(" {}  {}", tr!("deadline", time = format.deadline(&end)), rest), rgb(colors.deadline.0))?;
+        }
+        let elapsed_text = if is_paused {
+            tr!("elapsed_paused", time = format.duration(elapsed))
+        } else {
+            tr!("elapsed", time = format.duration(elapsed))
+        };
+        header(out, width, bg, format!(" {}", elapsed_text), rgb(colors.text.0))?;
+        header(out, width, bg, format!(" {}", s.name), rgb(colors.text.0))?;
+        queue!(out, Print("─".repeat(width.min(60))), Print("\r\n"))?;
+
+        // メモ（Markdown のサブセット）
+        let active = history::checklist_from_memo(&s.memo)
+            .iter()
+            .position(|c| !c.checked);
+        let mut item_index = 0usize;
+        for md in markdown::parse(&s.memo) {
+            queue!(out, Print(" ".repeat(md.indent)))?;
+            match &md.kind {
+                markdown::LineKind::Blank => {}
+                markdown::LineKind::Task { checked } => {
+                    let idx = item_index;
+                    item_index += 1;
+                    let cursor = if idx == self.selected { ">" } else { " " };
+                    let mark = if *checked { "[x]" } else { "[ ]" };
+                    queue!(out, Print(format!("{} {} ", cursor, mark)))?;
+                    if active == Some(idx) {
+                        queue!(out, SetAttribute(Attribute::Bold))?;
+                    }
+                    print_spans(out, &md.spans)?;
+                    let split = if *checked {
+                        s.splits().split(idx)
+                    } else if active == Some(idx) {
+                        Some(elapsed.saturating_sub(s.splits().last_tick()))
+                    } else {
+                        None
+                    };
+                    if let Some(d) = split {
+                        queue!(
+                            out,
+                            SetForegroundColor(Color::DarkGrey),
+                            Print(format!("  {}", format.duration(d))),
+                            ResetColor,
+                        )?;
+                    }
+                }
+                markdown::LineKind::Heading(_) => {
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                    print_spans(out, &md.spans)?;
+                }
+                markdown::LineKind::Bullet => {
+                    queue!(out, Print("• "))?;
+                    print_spans(out, &md.spans)?;
+                }
+                markdown::LineKind::Numbered(n) => {
+                    queue!(out, Print(format!("{}. ", n)))?;
+                    print_spans(out, &md.spans)?;
+                }
+                markdown::LineKind::Quote => {
+                    queue!(out, SetForegroundColor(Color::DarkGrey), Print("▍"), SetAttribute(Attribute::Italic))?;
+                    print_spans(out, &md.spans)?;
+                }
+                markdown::LineKind::Paragraph => print_spans(out, &md.spans)?,
+            }
+            queue!(out, SetAttribute(Attribute::Reset), ResetColor, Print("\r\n"))?;
+        }
+        queue!(out, Print("─".repeat(width.min(60))), Print("\r\n"))?;
+
+        if let Some(next) = s.queue.front() {
+            let preview = match next.estimate {
+                Some(m) => tr!("queue_next_estimate", name = next.name, minutes = m),
+                None => tr!("queue_next", name = next.name),
+            };
+            let more = if s.queue.len() > 1 { format!("  {}", tr!("queue_more", count = s.queue.len() - 1)) } else { String::new() };
+            queue!(out, SetForegroundColor(Color::DarkGrey), Print(preview), Print(more), ResetColor, Print("\r\n"))?;
+        }
+        if let Some(msg) = &s.clip_msg {
+            queue!(out, Print(msg), Print("\r\n"))?;
+        }
+        if let Some(err) = &s.config_error {
+            queue!(out, SetForegroundColor(Color::DarkRed), Print(err), ResetColor, Print("\r\n"))?;
+        }
+        match &self.prompt {
+            Some((kind, input)) => {
+                queue!(out, Print(format!("{}: {}", kind.label(), input)), cursor::Show)?;
+            }
+            None => {
+                queue!(
+                    out,
+                    cursor::Hide,
+                    SetForegroundColor(Color::DarkGrey),
+                    Print(tr!("tui_keys")),
+                    ResetColor,
+                )?;
+            }
+        }
+        out.flush()
+    }
+}
+
+// 見出しの1行を背景色付きで、端末の幅いっぱいに書く
+fn header(out: &mut impl Write, width: usize, bg: Color, text: String, fg: Color) -> io::Result<()> {
+    let pad = width.saturating_sub(display_width(&text));
+    queue!(
+        out,
+        SetBackgroundColor(bg),
+        SetForegroundColor(fg),
+        SetAttribute(Attribute::Bold),
+        Print(text),
+        Print(" ".repeat(pad)),
+        SetAttribute(Attribute::Reset),
+        ResetColor,
+        Print("\r\n"),
+    )
+}
+
+// インラインの装飾付きテキスト片を書く
+fn print_spans(out: &mut impl Write, spans: &[markdown::Span]) -> io::Result<()> {
+    for span in spans {
+        if span.bold {
+            queue!(out, SetAttribute(Attribute::Bold))?;
+        }
+        if span.italic {
+            queue!(out, SetAttribute(Attribute::Italic))?;
+        }
+        if span.strike {
+            queue!(out, SetAttribute(Attribute::CrossedOut))?;
+        }
+        if span.code {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        match &span.link {
+            Some(url) => queue!(
+                out,
+                SetAttribute(Attribute::Underlined),
+                Print(&span.text),
+                SetAttribute(Attribute::NoUnderline),
+                Print(format!(" <{}>", url)),
+            )?,
+            None => queue!(out, Print(&span.text))?,
+        }
+        // 行全体の装飾（取り組み中の項目の太字など）を残すため、個別の装飾だけを戻す
+        if span.bold {
+            queue!(out, SetAttribute(Attribute::NormalIntensity))?;
+        }
+        if span.italic {
+            queue!(out, SetAttribute(Attribute::NoItalic))?;
+        }
+        if span.strike {
+            queue!(out, SetAttribute(Attribute::NotCrossedOut))?;
+        }
+        if span.code {
+            queue!(out, SetAttribute(Attribute::NoReverse))?;
+        }
+    }
+    Ok(())
+}
+
+// 端末上の表示幅（全角文字は2桁として数える）
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if (c as u32) < 0x1100 || ('\u{ff61}'..='\u{ff9f}').contains(&c) { 1 } else { 2 })
+        .sum()
+}
@@ -0,0 +1,713 @@
+// 埋め込み用のウィジェット
+// ウィンドウに表示しているストップウォッチ（〆・経過時間・作業名・メモ・完了ボタン・キュー）を、
+// 任意の egui の Ui に表示できるようにしたもの。タイマーとメモの状態は Session が持ち、ここには画面の状態だけを置く。
+//
+//   let mut widget = StopwatchWidget::new(Session::new(...));
+//   egui::CentralPanel::default().show(ctx, |ui| {
+//       let output = widget.show(ui);
+//   });
+use crate::session::{self, Session};
+use crate::tr;
+use crate::{config, history, markdown};
+use eframe::egui::{self, Color32, Key, RichText, Sense};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// 1フレーム分の表示で起きたことのうち、埋め込み側で対応が必要なもの
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StopwatchOutput {
+    /// 設定ファイルを読み込み直した（色などを反映し直す）
+    pub config_reloaded: bool,
+    /// ポモドーロのフェーズが切り替わった
+    pub phase_changed: bool,
+    /// 最前面表示を切り替えた（新しい状態は StopwatchWidget::pinned）
+    pub pin_toggled: bool,
+    /// ミニバーと通常の表示を切り替えた（新しい状態は StopwatchWidget::compact）
+    pub compact_toggled: bool,
+    /// 表示内容を折り返さずに収める大きさ（ミニバーのときは None。ウィンドウの大きさを合わせるのに使う）
+    pub content_size: Option<egui::Vec2>,
+    /// ミニバーのドラッグが始まった（ウィンドウなら移動を始める）
+    pub drag_started: bool,
+    /// オーバーレイ表示を切り替えた（新しい状態は StopwatchWidget::overlay）
+    pub overlay_toggled: bool,
+    /// 完了してクリップボードにコピーし、キューに次の作業もない（アプリならここで終了する）
+    pub finished: bool,
+}
+
+/// ストップウォッチのウィジェット
+pub struct StopwatchWidget {
+    pub session: Session,
+    /// ウィンドウを最前面に表示しているか（Some ならボタンと Ctrl+T で切り替えられる）
+    pub pinned: Option<bool>,
+    /// 作業名と時間だけの1行のミニバーで表示する（ダブルクリックと、shortcuts なら Ctrl+M で切り替えられる）
+    pub compact: bool,
+    /// Ctrl+P（一時停止）と Ctrl+M（ミニバー）のキーを受け付けるか（埋め込み先のショートカットを奪わないよう既定は false）
+    pub shortcuts: bool,
+    /// クリックを透過するオーバーレイ表示か（Some なら Ctrl+O と ctl overlay で切り替えられる）
+    pub overlay: Option<bool>,
+    // 中断セッションの確認のために通常の表示に戻したか（答えたらミニバーに戻す）
+    compact_after_recovery: bool,
+    name_edit: bool,
+    // memo edit mode
+    memo_edit: bool,
+    // 最後に幅を測ったメモと、折り返さずに表示したときの幅
+    measured_memo: Option<(String, f32)>,
+}
+
+impl Default for StopwatchWidget {
+    fn default() -> Self {
+        Self::new(Session::new(
+            tr!("default_name").to_owned(),
+            tr!("sample_memo").to_owned(),
+            None,
+            None,
+            VecDeque::new(),
+            config::Config::default(),
+        ))
+    }
+}
+
+impl StopwatchWidget {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session,
+            pinned: None,
+            compact: false,
+            shortcuts: false,
+            overlay: None,
+            compact_after_recovery: false,
+            name_edit: false,
+            memo_edit: false,
+            measured_memo: None,
+        }
+    }
+
+    /// 作業名またはメモを編集中かどうか
+    pub fn is_editing(&self) -> bool {
+        self.name_edit || self.memo_edit
+    }
+
+    /// ui の残りの領域いっぱいに表示する（毎フレーム呼ぶ）
+    pub fn show(&mut self, ui: &mut egui::Ui) -> StopwatchOutput {
+        let ctx = ui.ctx().clone();
+        // 設定・メモファイルの再読み込み、制御 API、スナップショットの保存、フック
+        let polled = self.session.poll(self.memo_edit);
+        let mut output = StopwatchOutput {
+            config_reloaded: polled.config_reloaded,
+            phase_changed: polled.phase_changed,
+            pin_toggled: false,
+            compact_toggled: false,
+            content_size: None,
+            drag_started: false,
+            overlay_toggled: false,
+            finished: false,
+        };
+        let colors = self.session.config.colors.clone();
+        let sizes = self.session.config.font_sizes.clone();
+
+        // compute current time and elapsed first so we can decide background color
+        // 一時停止の切り替え（Ctrl+P）
+        if self.shortcuts && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, Key::P)) {
+            self.session.toggle_pause();
+        }
+        // 最前面表示の切り替え（Ctrl+T）
+        if let Some(pinned) = &mut self.pinned
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, Key::T))
+        {
+            *pinned = !*pinned;
+            output.pin_toggled = true;
+        }
+        // オーバーレイ表示の切り替え（Ctrl+O、制御 API の overlay）
+        let overlay_requested = self.session.overlay_requested.take();
+        if let Some(overlay) = &mut self.overlay {
+            let next = match overlay_requested {
+                Some(enabled) => enabled.unwrap_or(!*overlay),
+                None if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, Key::O)) => !*overlay,
+                None => *overlay,
+            };
+            if next != *overlay {
+                *overlay = next;
+                output.overlay_toggled = true;
+            }
+        }
+        // ミニバーの切り替え（Ctrl+M）
+        if self.shortcuts && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, Key::M)) {
+            self.toggle_compact(&mut output);
+        }
+        let is_paused = self.session.pauses.is_paused();
+
+        // 一時停止中は経過時間・残り時間を止めるため、一時停止分を差し引いて計算する
+        let now = Instant::now();
+        let elapsed = self.session.elapsed(now);
+        let end_time_local = self.session.deadline_local(now);
+        let remaining = self.session.remaining_secs(now);
+        let format = self.session.config.format;
+
+        // ポモドーロの現在のフェーズ
+        let phase = self.session.pomodoro_state(now);
+        // 一時停止中・休憩中・〆の超過で背景色を変える
+        let bgcolor = self.session.background_color(now).gamma_multiply(self.opacity());
+
+        // ミニバーには中断セッションの確認を表示できないので、答えるまで通常の表示にする
+        if self.compact && self.session.recovered.is_some() {
+            self.toggle_compact(&mut output);
+            self.compact_after_recovery = true;
+        } else if self.compact_after_recovery && self.session.recovered.is_none() {
+            self.compact_after_recovery = false;
+            if !self.compact {
+                self.toggle_compact(&mut output);
+            }
+        }
+        if self.compact {
+            self.show_compact(ui, &mut output, bgcolor, now);
+            return output;
+        }
+
+        new_frame(bgcolor).show(ui, |ui| {
+            ui.set_min_size(ui.available_size());
+            // 表示内容を折り返さずに収める幅（ウィンドウの大きさを合わせるために測る）
+            let top = ui.min_rect().top();
+            let mut natural_width: f32 = 0.0;
+            // Add a small left margin (~2mm) in pixels computed from points and
+            // the current pixels_per_point scaling so it respects DPI scaling.
+            let mm = 2.0_f32;
+            // convert mm -> inches -> points (1pt = 1/72 inch), then to pixels
+            let points = (mm / 25.4_f32) * 72.0_f32;
+            let left_px = points * ctx.pixels_per_point();
+
+            let content = ui.horizontal(|ui| {
+                ui.add_space(left_px);
+                ui.vertical(|ui| {
+
+                    // 前回中断したセッションがあれば、再開・記録・破棄を選んでもらう
+                    if let Some(snap) = &self.session.recovered {
+                        let snap_secs = snap.elapsed_secs(snap.saved_at);
+                        let mut choice: Option<session::RecoveryChoice> = None;
+                        ui.group(|ui| {
+                            ui.label(
+                                RichText::new(tr!(
+                                    "recovery_prompt",
+                                    name = snap.name,
+                                    start = format.date_time(&snap.start),
+                                    elapsed = format.duration(Duration::from_secs(snap_secs)),
+                                ))
+                                .color(colors.text.0),
+                            );
+                            ui.horizontal(|ui| {
+                                if ui.button(tr!("recovery_resume")).clicked() {
+                                    choice = Some(session::RecoveryChoice::Resume);
+                                }
+                                if ui.button(tr!("recovery_record")).clicked() {
+                                    choice = Some(session::RecoveryChoice::Record);
+                                }
+                                if ui.button(tr!("recovery_discard")).clicked() {
+                                    choice = Some(session::RecoveryChoice::Discard);
+                                }
+                            });
+                        });
+                        if let Some(c) = choice {
+                            self.session.resolve_recovery(c);
+                            self.name_edit = false;
+                            self.memo_edit = false;
+                        }
+                    }
+
+                    // ポモドーロのフェーズ・何回目か・フェーズの残り時間
+                    if let Some((p, t)) = phase {
+                        let cycles = self.session.config.pomodoro.cycles;
+                        let status = RichText::new(tr!(
+                            "pomodoro_status",
+                            phase = p.phase.label(),
+                            cycle = p.cycle,
+                            cycles = cycles,
+                            time = format.duration(p.end.saturating_sub(t)),
+                        ))
+                        .color(colors.text.0)
+                        .size(sizes.remaining)
+                        .strong();
+                        natural_width = natural_width.max(text_width(ui, &status));
+                        ui.label(status);
+                    }
+
+                    // 終了時刻（あれば）を上部に表示（〆の右に残り/経過時間を表示）
+                    if let Some(end_local) = &end_time_local {
+                        let end_label = tr!("deadline", time = format.deadline(end_local));
+                        // 〆までの残り時間、過ぎていれば超過時間
+                        let rest = remaining.map(|r| {
+                            let d = Duration::from_secs(r.unsigned_abs());
+                            if r >= 0 {
+                                tr!("remaining", time = format.duration(d))
+                            } else {
+                                tr!("overtime", time = format.duration(d))
+                            }
+                        });
+                        let row = ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(&end_label)
+                                    .color(colors.deadline.0)
+                                    .size(sizes.deadline)
+                                    .strong(),
+                            );
+                            if let Some(rest) = rest {
+                                ui.add_space(8.0);
+                                ui.label(
+                                    RichText::new(rest)
+                                        .color(colors.text.0)
+                                        .size(sizes.remaining)
+                                        .strong(),
+                                );
+                            }
+                        });
+                        natural_width = natural_width.max(row.response.rect.width());
+                    }
+
+                    // 経過時間を設定の形式（既定は mm:ss、1時間以上は h:mm:ss）で表示
+                    let elapsed_text = if is_paused {
+                        tr!("elapsed_paused", time = format.duration(elapsed))
+                    } else {
+                        tr!("elapsed", time = format.duration(elapsed))
+                    };
+                    let elapsed_text = RichText::new(elapsed_text)
+                        .color(colors.text.0)
+                        .size(sizes.elapsed)   // フォントサイズ（ポイント）
+                        .strong();        // 太字
+                    natural_width = natural_width.max(text_width(ui, &elapsed_text));
+                    let elapsed_label = ui.add(egui::Label::new(elapsed_text).sense(Sense::click()));
+                    // 経過時間をダブルクリックするとミニバーにする
+                    if elapsed_label.on_hover_text(tr!("compact_hint")).double_clicked() {
+                        self.toggle_compact(&mut output);
+                    }
+                    // 作業名表示: ラベルモード / 編集モードを切り替え
+                    if !self.name_edit {
+                        // Make task name larger and bold for prominence
+                        let label = RichText::new(&self.session.name)
+                            .color(colors.text.0)
+                            .size(sizes.name)
+                            .strong();
+                        natural_width = natural_width.max(text_width(ui, &label));
+                        if ui.add(egui::Label::new(label).sense(Sense::click())).clicked() {
+                            self.name_edit = true;
+                        }
+                    } else {
+                        // 編集モード: 1行入力。Esc で確定してラベルモードに戻る。
+                        let mut name_buf = self.session.name.clone();
+                        let _resp = ui.add(egui::TextEdit::singleline(&mut name_buf));
+                        // イベントで Escape を検出
+                        let events = ctx.input(|i| i.events.clone());
+                        let mut commit = false;
+                        for ev in events.iter() {
+                            if let egui::Event::Key { key, pressed, .. } = ev
+                                && *pressed
+                                && *key == Key::Escape
+                            {
+                                commit = true;
+                            }
+                        }
+                        // 反映
+                        self.session.name = name_buf;
+                        if commit {
+                            self.name_edit = false;
+                        }
+                    }
+
+                    // ----- メモ領域 -----
+                    ui.separator();
+                    if !self.memo_edit {
+                        // ラベルモード: Markdown（サブセット）として行ごとに表示
+                        natural_width = natural_width.max(self.memo_width(ui, elapsed));
+                        let (clicked, toggled) = self.show_memo(ui, elapsed);
+                        // テキストをクリックしたら編集モードへ
+                        if clicked {
+                            self.memo_edit = true;
+                        }
+                        // チェック時刻の記録とメモの書き換え
+                        if let Some(idx) = toggled {
+                            self.session.toggle_item(idx);
+                        }
+                    } else {
+                        // 編集モード: 複数行テキスト編集
+                        let mut edit = self.session.memo.clone();
+                        let _resp = ui.add(egui::TextEdit::multiline(&mut edit).desired_rows(6));
+                        // 入力イベントを見て Escape を確定キー、Alt+Enter を改行にする
+                        let mut commit = false;
+                        let events = ctx.input(|i| i.events.clone());
+                        for ev in events.iter() {
+                            if let egui::Event::Key { key, pressed, modifiers, .. } = ev
+                                && *pressed
+                            {
+                                if *key == Key::Escape {
+                                    // Escape: 確定（編集終了）
+                                    commit = true;
+                                } else if *key == Key::Enter && modifiers.alt {
+                                    // Alt+Enter: 改行を挿入
+                                    edit.push('\n');
+                                }
+                            }
+                        }
+                        // 編集内容を常に反映
+                        self.session.memo = edit;
+                        if commit {
+                            self.memo_edit = false;
+                        }
+                    }
+                    // 完了ボタン: 押されたらセッションを履歴に記録し、
+                    // テンプレート（既定は現在時刻の HHMM 形式）に従ってクリップボードに保存
+                    let mut done_clicked = std::mem::take(&mut self.session.done_requested);
+                    let buttons = ui.horizontal(|ui| {
+                        if ui.add(egui::Button::new(tr!("done"))).clicked() {
+                            done_clicked = true;
+                        }
+                        // 一時停止 / 再開ボタン（shortcuts なら Ctrl+P でも切り替え可能）
+                        let pause_label = if is_paused { tr!("resume") } else { tr!("pause") };
+                        if ui.add(egui::Button::new(pause_label)).clicked() {
+                            self.session.toggle_pause();
+                        }
+                        // 最前面表示の切り替え
+                        if let Some(pinned) = &mut self.pinned
+                            && ui.toggle_value(pinned, tr!("pin")).on_hover_text(tr!("pin_hint")).clicked()
+                        {
+                            output.pin_toggled = true;
+                        }
+                    });
+                    natural_width = natural_width.max(buttons.response.rect.width());
+                    if done_clicked {
+                        self.complete(&mut output);
+                    }
+
+                    if let Some(msg) = &self.session.clip_msg {
+                        ui.colored_label(colors.text.0, msg);
+                    }
+                    if let Some(err) = &self.session.config_error {
+                        ui.colored_label(Color32::DARK_RED, err);
+                    }
+
+                    // ----- キュー（次の作業） -----
+                    if let Some(next) = self.session.queue.front() {
+                        let preview = match next.estimate {
+                            Some(m) => tr!("queue_next_estimate", name = next.name, minutes = m),
+                            None => tr!("queue_next", name = next.name),
+                        };
+                        ui.label(RichText::new(preview).color(Color32::DARK_GRAY).size(12.0));
+                        // 並べ替え・スキップ用の一覧
+                        egui::CollapsingHeader::new(tr!("queue_header", count = self.session.queue.len()))
+                            .id_salt("queue")
+                            .show(ui, |ui| {
+                                let mut action: Option<(usize, i32)> = None;
+                                let len = self.session.queue.len();
+                                for (i, task) in self.session.queue.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        if ui.add_enabled(i > 0, egui::Button::new("▲").small()).clicked() {
+                                            action = Some((i, -1));
+                                        }
+                                        if ui.add_enabled(i + 1 < len, egui::Button::new("▼").small()).clicked() {
+                                            action = Some((i, 1));
+                                        }
+                                        if ui.add(egui::Button::new(tr!("queue_skip")).small()).clicked() {
+                                            action = Some((i, 0));
+                                        }
+                                        ui.label(RichText::new(&task.name).color(colors.text.0));
+                                    });
+                                }
+                                match action {
+                                    Some((i, 0)) => {
+                                        self.session.queue.remove(i);
+                                    }
+                                    Some((i, d)) => {
+                                        let j = (i as i32 + d) as usize;
+                                        self.session.queue.swap(i, j);
+                                    }
+                                    None => {}
+                                }
+                            });
+                    }
+
+                    // リアルタイム更新を促す
+                    ctx.request_repaint_after(Duration::from_millis(self.session.config.repaint_interval_ms));
+
+                    // チェックの切り替えや編集の確定をメモファイルに書き戻す
+                    if !self.memo_edit {
+                        self.session.sync_memo_file();
+                    }
+                });
+            });
+            output.content_size = Some(egui::vec2(
+                natural_width + left_px * 2.0,
+                content.response.rect.bottom() - top,
+            ));
+        });
+        output
+    }
+
+    // 完了: 履歴に記録してクリップボードにコピーし（Session で有効にした場合）、キューに次の作業があればそれを開始する
+    fn complete(&mut self, output: &mut StopwatchOutput) {
+        let clip_text = self.session.finish();
+        // コピーに失敗したらそのまま（もう一度「完了!」でやり直せる）
+        if self.session.clipboard_enabled {
+            match session::copy_to_clipboard(&clip_text) {
+                Ok(msg) => self.session.clip_msg = Some(msg),
+                Err(e) => {
+                    self.session.clip_msg = Some(e);
+                    return;
+                }
+            }
+        }
+        // キューに次の作業がなければ終了を知らせる
+        if !self.session.start_next() {
+            output.finished = true;
+        }
+        self.name_edit = false;
+        self.memo_edit = false;
+    }
+
+    // メモを Markdown（サブセット）として行ごとに表示する（テキストがクリックされたか、チェックを切り替えた項目を返す）
+    fn show_memo(&self, ui: &mut egui::Ui, elapsed: Duration) -> (bool, Option<usize>) {
+        let colors = &self.session.config.colors;
+        let sizes = &self.session.config.font_sizes;
+        let format = self.session.config.format;
+        let md_lines = markdown::parse(&self.session.memo);
+        // 現在取り組んでいるステップ = 最初の未チェック項目
+        let active = history::checklist_from_memo(&self.session.memo)
+            .iter()
+            .position(|c| !c.checked);
+        let mut item_index = 0usize;
+        let mut toggled: Option<usize> = None;
+        let mut memo_clicked = false;
+        for md in &md_lines {
+            if md.kind == markdown::LineKind::Blank {
+                ui.add_space(8.0);
+                continue;
+            }
+            ui.horizontal_wrapped(|ui| {
+                // インデント（入れ子のリスト）を保つ
+                ui.add_space(md.indent as f32 * 6.0);
+                let clicked = match &md.kind {
+                    markdown::LineKind::Task { checked } => {
+                        let idx = item_index;
+                        item_index += 1;
+                        let mut checked_bool = *checked;
+                        if ui.checkbox(&mut checked_bool, "").clicked() {
+                            toggled = Some(idx);
+                        }
+                        // 取り組み中のステップを強調表示
+                        let is_active = active == Some(idx);
+                        let style = SpanStyle {
+                            strong: is_active,
+                            background: is_active.then_some(Color32::WHITE),
+                            ..SpanStyle::new(colors.text.0)
+                        };
+                        let clicked = show_spans(ui, &md.spans, &style);
+                        // 所要時間: チェック済みなら確定値、取り組み中なら進行中の時間
+                        let split = if *checked {
+                            self.session.splits.split(idx)
+                        } else if is_active {
+                            Some(elapsed.saturating_sub(self.session.splits.last_tick()))
+                        } else {
+                            None
+                        };
+                        if let Some(d) = split {
+                            ui.add_space(6.0);
+                            ui.label(
+                                RichText::new(format.duration(d))
+                                    .color(Color32::DARK_GRAY)
+                                    .size(12.0),
+                            );
+                        }
+                        clicked
+                    }
+                    markdown::LineKind::Heading(level) => {
+                        let size = match level {
+                            1 => sizes.name,
+                            2 => sizes.name * 0.85,
+                            _ => sizes.name * 0.75,
+                        };
+                        let style = SpanStyle {
+                            strong: true,
+                            size: Some(size),
+                            ..SpanStyle::new(colors.text.0)
+                        };
+                        show_spans(ui, &md.spans, &style)
+                    }
+                    markdown::LineKind::Bullet => {
+                        ui.label(RichText::new("•").color(colors.text.0));
+                        show_spans(ui, &md.spans, &SpanStyle::new(colors.text.0))
+                    }
+                    markdown::LineKind::Numbered(n) => {
+                        ui.label(RichText::new(format!("{}.", n)).color(colors.text.0));
+                        show_spans(ui, &md.spans, &SpanStyle::new(colors.text.0))
+                    }
+                    markdown::LineKind::Quote => {
+                        ui.label(RichText::new("▍").color(Color32::GRAY));
+                        let style = SpanStyle {
+                            italic: true,
+                            ..SpanStyle::new(Color32::DARK_GRAY)
+                        };
+                        show_spans(ui, &md.spans, &style)
+                    }
+                    markdown::LineKind::Paragraph | markdown::LineKind::Blank => {
+                        show_spans(ui, &md.spans, &SpanStyle::new(colors.text.0))
+                    }
+                };
+                memo_clicked |= clicked;
+            });
+        }
+        (memo_clicked, toggled)
+    }
+
+    // メモを折り返さずに表示したときの幅（メモが変わったときだけ見えない Ui に表示して測り直す）
+    fn memo_width(&mut self, ui: &mut egui::Ui, elapsed: Duration) -> f32 {
+        if let Some((memo, width)) = &self.measured_memo
+            && *memo == self.session.memo
+        {
+            return *width;
+        }
+        let rect = egui::Rect::from_min_size(ui.cursor().min, egui::vec2(10000.0, 10000.0));
+        let mut sizing = ui.new_child(
+            egui::UiBuilder::new()
+                .id_salt("memo_sizing")
+                .max_rect(rect)
+                .layout(egui::Layout::top_down(egui::Align::Min))
+                .sizing_pass()
+                .invisible(),
+        );
+        sizing.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+        self.show_memo(&mut sizing, elapsed);
+        let width = sizing.min_rect().width();
+        self.measured_memo = Some((self.session.memo.clone(), width));
+        width
+    }
+
+    /// 背景の不透明度（オーバーレイ表示中は window.overlay_opacity）
+    pub fn opacity(&self) -> f32 {
+        let window = &self.session.config.window;
+        if self.overlay == Some(true) {
+            window.overlay_opacity
+        } else {
+            window.opacity
+        }
+    }
+
+    fn toggle_compact(&mut self, output: &mut StopwatchOutput) {
+        self.compact = !self.compact;
+        self.name_edit = false;
+        self.memo_edit = false;
+        output.compact_toggled = true;
+    }
+
+    // ミニバー: 作業名と時間（〆があれば残り時間、過ぎていれば +超過時間、なければ経過時間）だけを1行で表示する
+    fn show_compact(&mut self, ui: &mut egui::Ui, output: &mut StopwatchOutput, bgcolor: Color32, now: Instant) {
+        // 制御 API から完了を要求されたときはミニバーのまま完了する
+        if std::mem::take(&mut self.session.done_requested) {
+            self.complete(output);
+        }
+        let colors = self.session.config.colors.clone();
+        let size = self.session.config.font_sizes.remaining;
+        let format = self.session.config.format;
+        let (time, time_color) = match self.session.remaining_secs(now) {
+            Some(r) if r >= 0 => (format.duration(Duration::from_secs(r as u64)), colors.deadline.0),
+            Some(r) => (format!("+{}", format.duration(Duration::from_secs(r.unsigned_abs()))), colors.deadline.0),
+            None => (format.duration(self.session.elapsed(now)), colors.text.0),
+        };
+        let bar = new_frame(bgcolor)
+            .inner_margin(egui::Margin::symmetric(8, 2))
+            .show(ui, |ui| {
+                ui.set_min_size(ui.available_size());
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.add(
+                        egui::Label::new(RichText::new(time).color(time_color).size(size).strong())
+                            .selectable(false),
+                    );
+                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                        ui.add(
+                            egui::Label::new(
+                                RichText::new(&self.session.name).color(colors.text.0).size(size).strong(),
+                            )
+                            .selectable(false)
+                            .truncate(),
+                        );
+                    });
+                });
+            })
+            .response;
+        // バー全体でダブルクリック（元に戻す）とドラッグ（移動）を受け付ける
+        let bar = ui
+            .interact(bar.rect, ui.id().with("compact_bar"), Sense::click_and_drag())
+            .on_hover_text(tr!("expand_hint"));
+        if bar.double_clicked() {
+            self.toggle_compact(output);
+        } else if bar.drag_started() {
+            output.drag_started = true;
+        }
+        ui.ctx()
+            .request_repaint_after(Duration::from_millis(self.session.config.repaint_interval_ms));
+        self.session.sync_memo_file();
+    }
+}
+
+// Markdown のテキスト片を表示するときの基本の見た目
+struct SpanStyle {
+    color: Color32,
+    size: Option<f32>,
+    strong: bool,
+    italic: bool,
+    background: Option<Color32>,
+}
+
+impl SpanStyle {
+    fn new(color: Color32) -> Self {
+        Self {
+            color,
+            size: None,
+            strong: false,
+            italic: false,
+            background: None,
+        }
+    }
+}
+
+// インラインの装飾付きテキスト片を並べて表示する（リンク以外をクリックしたら true）
+fn show_spans(ui: &mut egui::Ui, spans: &[markdown::Span], style: &SpanStyle) -> bool {
+    ui.spacing_mut().item_spacing.x = 0.0;
+    let mut clicked = false;
+    for span in spans {
+        let mut text = RichText::new(&span.text).color(style.color);
+        if let Some(size) = style.size {
+            text = text.size(size);
+        }
+        if style.strong || span.bold {
+            text = text.strong();
+        }
+        if style.italic || span.italic {
+            text = text.italics();
+        }
+        if span.strike {
+            text = text.strikethrough();
+        }
+        if span.code {
+            text = text.code();
+        } else if let Some(bg) = style.background {
+            text = text.background_color(bg);
+        }
+        if let Some(url) = &span.link {
+            ui.hyperlink_to(text.color(Color32::BLUE).underline(), url);
+        } else if ui.add(egui::Label::new(text).sense(Sense::click())).clicked() {
+            clicked = true;
+        }
+    }
+    clicked
+}
+
+// テキストを折り返さずに表示したときの幅
+fn text_width(ui: &egui::Ui, text: &RichText) -> f32 {
+    egui::WidgetText::from(text.clone())
+        .into_galley(ui, Some(egui::TextWrapMode::Extend), f32::INFINITY, egui::TextStyle::Body)
+        .size()
+        .x
+}
+
+fn new_frame(bgcolor: egui::Color32) -> egui::Frame {
+    // Use the current egui API: Frame::new(), CornerRadius::same, Stroke::new
+    egui::Frame::new()
+        .fill(bgcolor)
+        .stroke(egui::Stroke::new(0.0, egui::Color32::BLACK))
+        .corner_radius(egui::CornerRadius::same(0))
+}